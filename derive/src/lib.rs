@@ -1,82 +1,424 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
 
-#[proc_macro_derive(AbstractState)]
+/// A struct or variant field as seen by the derive.
+struct Field<'a> {
+    /// Member used to access the field, e.g. `name` or `0`.
+    member: TokenStream2,
+    /// Field name used in reports, e.g. `"name"` or `"0"`.
+    name: String,
+    /// Field type.
+    ty: &'a Type,
+    /// Mismatches only warn (`#[state(relaxed)]`).
+    relaxed: bool,
+    /// Not checked or updated at all (`#[state(skip)]`).
+    skip: bool,
+}
+
+/// A field together with the expressions accessing it on both sides, e.g.
+/// `self.name` and `other.name`, or bindings of a matched enum variant.
+struct Access<'a> {
+    field: &'a Field<'a>,
+    lhs: TokenStream2,
+    rhs: TokenStream2,
+}
+
+/// Collect the fields of a struct or variant, named or unnamed.
+fn struct_fields(fields: &Fields) -> syn::Result<Vec<Field<'_>>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let (member, name) = match &f.ident {
+                Some(ident) => (quote!(#ident), ident.to_string()),
+                None => {
+                    let index = syn::Index::from(i);
+                    (quote!(#index), i.to_string())
+                }
+            };
+            let mut relaxed = false;
+            let mut skip = false;
+            for attr in f.attrs.iter().filter(|a| a.path().is_ident("state")) {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("relaxed") {
+                        relaxed = true;
+                        Ok(())
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported state attribute"))
+                    }
+                })?;
+            }
+            Ok(Field {
+                member,
+                name,
+                ty: &f.ty,
+                relaxed,
+                skip,
+            })
+        })
+        .collect()
+}
+
+#[proc_macro_derive(AbstractState, attributes(state))]
 pub fn derive_abstract_state(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    let gen_tokens = match &input.data {
+        Data::Struct(data) => derive_struct(&input, &data.fields),
+        Data::Enum(data) => derive_enum(&input, data),
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "AbstractState cannot be derived for unions",
+        )),
+    };
+    gen_tokens.unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+/// Derive for a struct, checking all fields that are not skipped.
+fn derive_struct(input: &DeriveInput, fields: &Fields) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = struct_fields(fields)?;
+    let accesses: Vec<_> = fields
+        .iter()
+        .filter(|f| !f.skip)
+        .map(|f| {
+            let member = &f.member;
+            Access {
+                field: f,
+                lhs: quote!(self.#member),
+                rhs: quote!(other.#member),
+            }
+        })
+        .collect();
+    // Avoid unused variable warnings if no field is checked.
+    let other = if accesses.is_empty() {
+        quote!(_other)
+    } else {
+        quote!(other)
+    };
+    let matches_impl = matches_fields(&accesses);
+    let update_impl = update_fields(&accesses);
+    let try_update_impl = try_update_fields(&accesses);
+    let diff_impl = diff_fields(&accesses);
+    let mismatch_level_impl = mismatch_level_fields(&accesses);
+    let observe_impl = observe_fields(&accesses);
+    let check_levels_impl = fields.iter().map(|f| {
+        let field_str = &f.name;
+        let level = if f.skip {
+            quote!(::km_checker::CheckLevel::None)
+        } else if f.relaxed {
+            quote!(::km_checker::CheckLevel::Relaxed)
+        } else {
+            quote!(::km_checker::CheckLevel::Strict)
+        };
+        quote! { (#field_str, #level) }
+    });
+    let field_kinds_impl = fields.iter().map(|f| {
+        let field_str = &f.name;
+        let kind = if f.skip {
+            quote!(::km_checker::state::FieldKind::Ignored)
+        } else {
+            field_kind(f.ty)
+        };
+        quote! { (#field_str, #kind) }
+    });
+    Ok(quote! {
+        impl AbstractState for #name {
+            fn matches(&self, #other: &Self) -> bool {
+                #matches_impl
+                true
+            }
+            fn update(&mut self, #other: &Self) {
+                #update_impl
+            }
+            fn try_update(&mut self, #other: &Self) -> Result<(), ::km_checker::Error> {
+                #try_update_impl
+                Ok(())
+            }
+            #[allow(unused_mut)]
+            fn diff(&self, #other: &Self) -> ::km_checker::__private::Vec<::km_checker::__private::String> {
+                #diff_impl
+            }
+            #[allow(unused_mut)]
+            fn mismatch_level(&self, #other: &Self) -> Option<::km_checker::CheckLevel> {
+                #mismatch_level_impl
+            }
+            fn observe(&mut self, #other: &Self) {
+                #observe_impl
+            }
+        }
+
+        impl #name {
+            /// Names and checking semantics of the fields.
+            pub fn field_kinds() -> &'static [(&'static str, ::km_checker::state::FieldKind)] {
+                &[ #( #field_kinds_impl ),* ]
+            }
+            /// Names and check levels of the fields, `Relaxed` for
+            /// `#[state(relaxed)]` fields.
+            pub fn check_levels() -> &'static [(&'static str, ::km_checker::CheckLevel)] {
+                &[ #( #check_levels_impl ),* ]
+            }
+        }
+    })
+}
+
+/// Derive for an enum. Values match if they are the same variant and all
+/// fields of the variant match. Updating to a different variant replaces the
+/// value, including skipped fields, so the enum must implement `Clone`.
+fn derive_enum(input: &DeriveInput, data: &syn::DataEnum) -> syn::Result<TokenStream2> {
     let name = &input.ident;
+    let variants = data
+        .variants
+        .iter()
+        .map(|v| Ok((v, struct_fields(&v.fields)?)))
+        .collect::<syn::Result<Vec<_>>>()?;
 
-    let gen_tokens = match &input.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => {
-                let matches_impl = fields.named.iter().map(|f| {
-                    let field_name = &f.ident;
-                    quote! {
-                        if !self.#field_name.matches(&other.#field_name) {
-                            return false;
-                        }
-                    }
-                });
-                let update_impl = fields.named.iter().map(|f| {
-                    let field_name = &f.ident;
-                    quote! {
-                        self.#field_name.update(&other.#field_name);
-                    }
-                });
-                quote! {
-                    impl AbstractState for #name {
-                        fn matches(&self, other: &Self) -> bool {
-                            #( #matches_impl )*
-                            true
-                        }
-                        fn update(&mut self, other: &Self) {
-                            #( #update_impl )*
-                        }
-                    }
+    let mut matches_arms = Vec::new();
+    let mut update_arms = Vec::new();
+    let mut try_update_arms = Vec::new();
+    let mut diff_arms = Vec::new();
+    let mut mismatch_level_arms = Vec::new();
+    let mut observe_arms = Vec::new();
+    let mut variant_names = Vec::new();
+    for (variant, fields) in &variants {
+        let ident = &variant.ident;
+        let variant_str = ident.to_string();
+        // Skipped fields are not bound.
+        let binding = |prefix: &str, f: &Field| {
+            if f.skip {
+                quote!(_)
+            } else {
+                let ident = format_ident!("{}{}", prefix, f.name);
+                quote!(#ident)
+            }
+        };
+        let lhs: Vec<_> = fields.iter().map(|f| binding("__self_", f)).collect();
+        let rhs: Vec<_> = fields.iter().map(|f| binding("__other_", f)).collect();
+        let members = fields.iter().map(|f| &f.member);
+        let (lhs_pat, rhs_pat) = match &variant.fields {
+            Fields::Named(_) => {
+                let members: Vec<_> = members.collect();
+                (
+                    quote!(#name::#ident { #( #members: #lhs ),* }),
+                    quote!(#name::#ident { #( #members: #rhs ),* }),
+                )
+            }
+            Fields::Unnamed(_) => (
+                quote!(#name::#ident( #( #lhs ),* )),
+                quote!(#name::#ident( #( #rhs ),* )),
+            ),
+            Fields::Unit => (quote!(#name::#ident), quote!(#name::#ident)),
+        };
+        let accesses: Vec<_> = fields
+            .iter()
+            .zip(lhs.iter().zip(rhs.iter()))
+            .filter(|(f, _)| !f.skip)
+            .map(|(f, (l, r))| Access {
+                field: f,
+                lhs: quote!(#l),
+                rhs: quote!(#r),
+            })
+            .collect();
+        let arm = quote!((#lhs_pat, #rhs_pat));
+        let matches_impl = matches_fields(&accesses);
+        let update_impl = update_fields(&accesses);
+        let try_update_impl = try_update_fields(&accesses);
+        let diff_impl = diff_fields(&accesses);
+        let mismatch_level_impl = mismatch_level_fields(&accesses);
+        let observe_impl = observe_fields(&accesses);
+        matches_arms.push(quote!(#arm => { #matches_impl true }));
+        update_arms.push(quote!(#arm => { #update_impl return; }));
+        try_update_arms.push(quote!(#arm => { #try_update_impl return Ok(()); }));
+        diff_arms.push(quote!(#arm => { #diff_impl }));
+        mismatch_level_arms.push(quote!(#arm => { #mismatch_level_impl }));
+        observe_arms.push(quote!(#arm => { #observe_impl }));
+        variant_names.push(quote!(#name::#ident { .. } => #variant_str));
+    }
+
+    Ok(quote! {
+        impl AbstractState for #name {
+            #[allow(unreachable_patterns)]
+            fn matches(&self, other: &Self) -> bool {
+                match (self, other) {
+                    #( #matches_arms )*
+                    _ => false,
                 }
             }
-            Fields::Unnamed(fields) => {
-                let matches_impl = fields.unnamed.iter().enumerate().map(|(i, _)| {
-                    let index = syn::Index::from(i);
-                    quote! {
-                        if !self.#index.matches(&other.#index) {
-                            return false;
-                        }
-                    }
-                });
-                let update_impl = fields.unnamed.iter().enumerate().map(|(i, _)| {
-                    let index = syn::Index::from(i);
-                    quote! {
-                        self.#index.update(&other.#index);
-                    }
-                });
-                quote! {
-                    impl AbstractState for #name {
-                        fn matches(&self, other: &Self) -> bool {
-                            #( #matches_impl )*
-                            true
-                        }
-                        fn update(&mut self, other: &Self) {
-                            #( #update_impl )*
-                        }
-                    }
+            #[allow(unreachable_patterns, unreachable_code)]
+            fn update(&mut self, other: &Self) {
+                match (&mut *self, other) {
+                    #( #update_arms )*
+                    _ => {}
+                }
+                *self = other.clone();
+            }
+            #[allow(unreachable_patterns, unreachable_code)]
+            fn try_update(&mut self, other: &Self) -> Result<(), ::km_checker::Error> {
+                match (&mut *self, other) {
+                    #( #try_update_arms )*
+                    _ => {}
                 }
+                *self = other.clone();
+                Ok(())
             }
-            Fields::Unit => {
-                quote! {
-                    impl AbstractState for #name {
-                        fn matches(&self, other: &Self) -> bool {
-                            true
-                        }
-                        fn update(&mut self, _other: &Self) {}
+            #[allow(unreachable_patterns, unused_mut)]
+            fn diff(&self, other: &Self) -> ::km_checker::__private::Vec<::km_checker::__private::String> {
+                fn variant(state: &#name) -> &'static str {
+                    match state {
+                        #( #variant_names, )*
                     }
                 }
+                match (self, other) {
+                    #( #diff_arms )*
+                    _ => ::km_checker::__private::vec![::km_checker::__private::format!(
+                        ": expected variant {}, got {}",
+                        variant(other),
+                        variant(self)
+                    )],
+                }
             }
-        },
-        _ => unimplemented!(),
-    };
+            #[allow(unreachable_patterns, unused_mut)]
+            fn mismatch_level(&self, other: &Self) -> Option<::km_checker::CheckLevel> {
+                match (self, other) {
+                    #( #mismatch_level_arms )*
+                    _ => Some(::km_checker::CheckLevel::Strict),
+                }
+            }
+            #[allow(unreachable_patterns)]
+            fn observe(&mut self, other: &Self) {
+                match (&mut *self, other) {
+                    #( #observe_arms )*
+                    _ => {}
+                }
+            }
+        }
+    })
+}
 
-    gen_tokens.into()
+/// Return `false` from the generated function on the first mismatching field.
+fn matches_fields(accesses: &[Access]) -> TokenStream2 {
+    let checks = accesses.iter().map(|a| {
+        let (lhs, rhs) = (&a.lhs, &a.rhs);
+        quote! {
+            if !#lhs.matches(&#rhs) {
+                return false;
+            }
+        }
+    });
+    quote!(#( #checks )*)
+}
+
+/// Update every field.
+fn update_fields(accesses: &[Access]) -> TokenStream2 {
+    let updates = accesses.iter().map(|a| {
+        let (lhs, rhs) = (&a.lhs, &a.rhs);
+        quote! {
+            #lhs.update(&#rhs);
+        }
+    });
+    quote!(#( #updates )*)
+}
+
+/// Observe every field.
+fn observe_fields(accesses: &[Access]) -> TokenStream2 {
+    let observes = accesses.iter().map(|a| {
+        let (lhs, rhs) = (&a.lhs, &a.rhs);
+        quote! {
+            #lhs.observe(&#rhs);
+        }
+    });
+    quote!(#( #observes )*)
+}
+
+/// Fallibly update every field. Inner `UpdateFailed` errors keep their (more
+/// specific) field name, other errors are reported as a failure of the field.
+fn try_update_fields(accesses: &[Access]) -> TokenStream2 {
+    let updates = accesses.iter().map(|a| {
+        let (lhs, rhs) = (&a.lhs, &a.rhs);
+        let field_str = &a.field.name;
+        quote! {
+            if let Err(e) = #lhs.try_update(&#rhs) {
+                return Err(match e {
+                    ::km_checker::Error::UpdateFailed(name) => ::km_checker::Error::UpdateFailed(name),
+                    _ => ::km_checker::Error::UpdateFailed(#field_str),
+                });
+            }
+        }
+    });
+    quote!(#( #updates )*)
+}
+
+/// Collect the differences of every field that mismatches according to its
+/// `mismatch_level`, prefixed with `.field`. A mismatching field whose type
+/// gives no details is reported as `.field: mismatch`, so the field is always
+/// named.
+fn diff_fields(accesses: &[Access]) -> TokenStream2 {
+    let diffs = accesses.iter().map(|a| {
+        let (lhs, rhs) = (&a.lhs, &a.rhs);
+        let prefix = format!(".{}", a.field.name);
+        let fallback = format!("{}: mismatch", prefix);
+        quote! {
+            if #lhs.mismatch_level(&#rhs).is_some() {
+                let field_diffs = #lhs.diff(&#rhs);
+                if field_diffs.is_empty() {
+                    diffs.push(::km_checker::__private::String::from(#fallback));
+                }
+                diffs.extend(
+                    field_diffs
+                        .into_iter()
+                        .map(|d| ::km_checker::__private::format!("{}{}", #prefix, d)),
+                );
+            }
+        }
+    });
+    quote! {
+        let mut diffs = ::km_checker::__private::Vec::new();
+        #( #diffs )*
+        diffs
+    }
+}
+
+/// Evaluate to the most severe mismatch level of all fields, capping
+/// relaxed fields at `Relaxed`.
+fn mismatch_level_fields(accesses: &[Access]) -> TokenStream2 {
+    let levels = accesses.iter().map(|a| {
+        let (lhs, rhs) = (&a.lhs, &a.rhs);
+        let cap = if a.field.relaxed {
+            quote!(.map(|l| l.min(::km_checker::CheckLevel::Relaxed)))
+        } else {
+            quote!()
+        };
+        quote! {
+            level = level.max(#lhs.mismatch_level(&#rhs) #cap);
+        }
+    });
+    quote! {
+        let mut level = None;
+        #( #levels )*
+        level
+    }
+}
+
+/// Guess the checking semantics of a field from the name of its type.
+fn field_kind(ty: &Type) -> TokenStream2 {
+    let ident = match ty {
+        Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        Type::Reference(_) => Some("str".to_string()),
+        _ => None,
+    };
+    match ident.as_deref() {
+        Some("Ignored") => quote!(::km_checker::state::FieldKind::Ignored),
+        Some(
+            "Value" | "ValueList" | "ValueSet" | "ValueMap" | "ValueMultiset" | "ValueSubset"
+            | "ValueWindow" | "FilteredList" | "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64"
+            | "i64" | "u128" | "i128" | "usize" | "isize" | "f32" | "f64" | "bool" | "char"
+            | "String" | "str",
+        ) => quote!(::km_checker::state::FieldKind::Value),
+        _ => quote!(::km_checker::state::FieldKind::Nested),
+    }
 }
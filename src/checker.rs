@@ -1,8 +1,20 @@
-use crate::{port::TestPort, AbstractState, Commander, Error, Printer};
+#[cfg(feature = "std")]
+use crate::event::escape_json;
+#[cfg(feature = "std")]
+use crate::Clock;
+use crate::{
+    port::TestPort, stats::command_name, AbstractState, ColorMode, CommandStats, Commander,
+    CommanderOutcome, Error, Event, MismatchKind, MismatchReport, MockTestPort, Printer, RetvKind,
+    Stats,
+};
+use alloc::collections::BTreeMap;
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
 use core::fmt::Debug;
+#[cfg(feature = "std")]
+use std::{io::Write, time::Instant};
 
-/// Check level (of retv and state).
-#[derive(Debug, PartialEq, Eq)]
+/// Check level (of retv and state), ordered by severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum CheckLevel {
     /// No checking.
     None,
@@ -12,6 +24,27 @@ pub enum CheckLevel {
     Strict,
 }
 
+/// Outcome of the checks of one round, passed to `on_after_check` hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckOutcome {
+    /// Whether the return value matched, `None` if not checked.
+    pub retv_matched: Option<bool>,
+    /// Whether the state matched, `None` if not checked.
+    pub state_matched: Option<bool>,
+}
+
+impl CheckOutcome {
+    /// Check if nothing mismatched.
+    pub fn passed(&self) -> bool {
+        self.retv_matched != Some(false) && self.state_matched != Some(false)
+    }
+}
+
+/// Hook called with the round number.
+type RoundHook = Box<dyn FnMut(usize)>;
+/// Hook called with the round number and the outcome of its checks.
+type CheckHook = Box<dyn FnMut(usize, CheckOutcome)>;
+
 /// Checker execution steps.
 enum CheckStep {
     /// Start of execution.
@@ -26,6 +59,8 @@ enum CheckStep {
     GetState,
     /// Checker check state.
     CheckState,
+    /// Commander finished, no more steps.
+    Finished,
 }
 
 /// Model Checker.
@@ -48,8 +83,50 @@ where
     round: usize,
     /// Current execution step.
     step: CheckStep,
+    /// Description of last command.
+    description: String,
+    /// `Debug` form of last command, which identifies it for replay.
+    replay_key: String,
     /// Return value of last command.
     retv: isize,
+    /// Return value of last command on the target.
+    test_retv: isize,
+    /// Whether the return value of last command matched, if checked.
+    retv_matched: Option<bool>,
+    /// Return value kind of last command.
+    retv_kind: RetvKind,
+    /// Whether the last command is a probe.
+    probe: bool,
+    /// Acceptable return values of last command, if not only `retv`.
+    acceptable_retvs: Option<Vec<isize>>,
+    /// Checking statistics.
+    stats: Stats,
+    /// Print diagnostics every N rounds.
+    diagnostics_every: Option<usize>,
+    /// Maximum number of mismatches before aborting.
+    max_mismatches: Option<usize>,
+    /// Whether to color the output.
+    color: ColorMode,
+    /// Whether to print state changes instead of full states.
+    delta_output: bool,
+    /// Pretty `Debug` representation of the model state before the last
+    /// command, in delta mode.
+    last_state: String,
+    /// Time when checking started.
+    #[cfg(feature = "std")]
+    start_time: Option<Instant>,
+    /// Sink of the per-round NDJSON trace.
+    #[cfg(feature = "std")]
+    trace: Option<Box<dyn Write>>,
+    /// Clock of the event and trace timestamps, if enabled.
+    #[cfg(feature = "std")]
+    clock: Option<Clock>,
+    /// Hook called before getting a command.
+    before_command: Option<RoundHook>,
+    /// Hook called after sending a command.
+    after_command: Option<RoundHook>,
+    /// Hook called after the checks of a round.
+    after_check: Option<CheckHook>,
 }
 
 impl<C, T, P, S> Checker<C, T, P, S>
@@ -68,18 +145,132 @@ where
             state,
             round: 0,
             step: CheckStep::Start,
+            description: String::new(),
+            replay_key: String::new(),
             retv: 0,
+            test_retv: 0,
+            retv_matched: None,
+            retv_kind: RetvKind::Signed,
+            probe: false,
+            acceptable_retvs: None,
+            stats: Stats::default(),
+            diagnostics_every: None,
+            max_mismatches: None,
+            color: ColorMode::Always,
+            delta_output: false,
+            last_state: String::new(),
+            #[cfg(feature = "std")]
+            start_time: None,
+            #[cfg(feature = "std")]
+            trace: None,
+            #[cfg(feature = "std")]
+            clock: None,
+            before_command: None,
+            after_command: None,
+            after_check: None,
         }
     }
 
+    /// Start building a checker with named components and options.
+    pub fn builder() -> CheckerBuilder<C, T, P, S> {
+        CheckerBuilder::new()
+    }
+
+    /// Print a diagnostics line and emit an `Event::Diagnostics` every
+    /// `every` rounds, or never if `None`.
+    pub fn set_diagnostics_every(&mut self, every: Option<usize>) {
+        self.diagnostics_every = every;
+    }
+
+    /// Fail with `TooManyMismatches` once more than `max` mismatches (relaxed
+    /// or strict) happened, or never if `None`.
+    ///
+    /// The count is checked at the end of each round, so relaxed runs keep
+    /// going past isolated mismatches but stop once the model clearly diverged.
+    /// A round ending with a strict mismatch returns `TooManyMismatches`
+    /// instead of the mismatch error once the limit is exceeded, so stepping
+    /// on after strict mismatches stops there as well.
+    pub fn set_max_mismatches(&mut self, max: Option<usize>) {
+        self.max_mismatches = max;
+    }
+
+    /// Set when to color the printed headlines and mismatches, `Always` by
+    /// default.
+    pub fn set_color_mode(&mut self, color: ColorMode) {
+        self.color = color;
+    }
+
+    /// Print only the lines of the (pretty `Debug`) states that changed since
+    /// the last round instead of full states on mismatches.
+    ///
+    /// The initial state is always printed in full.
+    pub fn set_delta_output(&mut self, enabled: bool) {
+        self.delta_output = enabled;
+    }
+
+    /// Write a trace of every round to `sink`, one JSON object per line.
+    ///
+    /// Each entry holds the round, the `Debug` form of the command (not its
+    /// free-form `describe`, so that `ReplayCommander::from_names` can rebuild
+    /// it), the model and target return values, whether the state matched
+    /// (`null` if not checked) and the `time` if a clock is set.
+    #[cfg(feature = "std")]
+    pub fn set_trace_sink(&mut self, sink: Box<dyn Write>) {
+        self.trace = Some(sink);
+    }
+
+    /// Timestamp events and trace entries with `clock`, or not if `None`
+    /// (the default). A monotonic clock counts from the start of checking.
+    #[cfg(feature = "std")]
+    pub fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+    }
+
+    /// Call `f` with the upcoming round number before getting each command.
+    pub fn on_before_command(&mut self, f: Box<dyn FnMut(usize)>) {
+        self.before_command = Some(f);
+    }
+
+    /// Call `f` with the round number after each command was sent.
+    pub fn on_after_command(&mut self, f: Box<dyn FnMut(usize)>) {
+        self.after_command = Some(f);
+    }
+
+    /// Call `f` with the round number and the check outcome after the
+    /// checks of each round, including rounds with (relaxed or strict)
+    /// mismatches.
+    pub fn on_after_check(&mut self, f: Box<dyn FnMut(usize, CheckOutcome)>) {
+        self.after_check = Some(f);
+    }
+
     /// Checker can be regarded as a finite state machine. This is the state transition function.
     ///
     /// State is transited as follows:
     ///
     /// Start -> Init* -> Command -> CheckRetv -> GetState* -> CheckState -> Command -> ...
+    ///
+    /// Once the commander reports `Finished`, the checker stays in a terminal
+    /// step and further calls do nothing. After a strict mismatch, the next
+    /// call continues with the next command. A `TargetFault` from the port
+    /// is a hard stop: the last command is reported as the likely culprit
+    /// and the checker enters the terminal step.
     pub fn step(&mut self, retv_level: CheckLevel, state_level: CheckLevel) -> Result<(), Error> {
+        let result = self.transit(retv_level, state_level);
+        if let Err(Error::TargetFault(fault)) = &result {
+            self.report_fault(fault);
+            self.step = CheckStep::Finished;
+        }
+        result
+    }
+
+    /// Perform one step transition, see `step`.
+    fn transit(&mut self, retv_level: CheckLevel, state_level: CheckLevel) -> Result<(), Error> {
         match self.step {
             CheckStep::Start => {
+                #[cfg(feature = "std")]
+                {
+                    self.start_time = Some(Instant::now());
+                }
                 // Start retrieving initial state from target.
                 self.port.start_state_retrieval()?;
                 self.step = CheckStep::GetState;
@@ -87,37 +278,131 @@ where
             CheckStep::Init => {
                 // Finish state retrieval, update self.
                 let init_state = self.port.finish_state_retrieval()?;
-                self.state.update(&init_state);
+                self.state.try_update(&init_state)?;
+                let state = format!("{:?}", self.state);
                 self.printer.print("[ Initial State ]");
-                self.printer.print(&format!("{:?}", self.state));
+                self.printer.print(&state);
+                self.printer.event(&Event::Init {
+                    state: &state,
+                    time: self.now(),
+                });
                 self.step = CheckStep::Command;
             }
             CheckStep::Command => {
-                self.round += 1;
-                self.printer
-                    .print(&format!("\x1b[1;32m[ Round {} ]\x1b[0m", self.round));
+                if let Some(hook) = &mut self.before_command {
+                    hook(self.round + 1);
+                }
                 // Get command from commander.
-                let command = self.commander.command(&self.state)?;
-                self.printer.print(&format!("Command: {:?}", command));
+                let command = match self.commander.command(&self.state) {
+                    CommanderOutcome::Command(command) => command,
+                    CommanderOutcome::Finished => {
+                        self.print_summary(&self.color.paint("1;32", "[ Finished ]"));
+                        self.printer.event(&Event::Finished {
+                            rounds: self.stats.rounds,
+                            failed_rounds: self.stats.failed_rounds,
+                            time: self.now(),
+                        });
+                        self.step = CheckStep::Finished;
+                        return Ok(());
+                    }
+                    CommanderOutcome::Error(e) => {
+                        let headline = format!("[ Commander failed in round {} ]", self.round + 1);
+                        self.print_summary(&self.color.paint("1;31", &headline));
+                        return Err(e);
+                    }
+                };
+                // Reject malformed commands before they count as a round.
+                if let Err(msg) = command.validate() {
+                    let msg = format!("Invalid command {}: {}", command.describe(), msg);
+                    self.printer.print(&self.color.paint("1;31", &msg));
+                    self.printer.flush();
+                    return Err(Error::InvalidCommand);
+                }
+                self.round += 1;
+                let headline = format!("[ Round {} ]", self.round);
+                self.printer.print(&self.color.paint("1;32", &headline));
+                let description = command.describe();
+                self.printer.print(&format!("Command: {}", description));
+                self.printer.event(&Event::Round {
+                    round: self.round,
+                    command: &description,
+                    time: self.now(),
+                });
+                self.description = description;
+                self.replay_key = format!("{:?}", command);
+                self.stats
+                    .record_command(command_name(&self.replay_key), command.category());
+                if self.delta_output {
+                    self.last_state = format!("{:#?}", self.state);
+                }
                 // Execute command on self state and record the return value.
                 self.retv = command.execute(&mut self.state);
+                self.retv_kind = command.retv_kind();
+                self.probe = command.is_probe();
+                self.acceptable_retvs = command.acceptable_retvs(&self.state);
                 // Send command to test port.
                 self.port.send_command(command.as_ref())?;
+                if let Some(hook) = &mut self.after_command {
+                    hook(self.round);
+                }
                 self.step = CheckStep::CheckRetv;
             }
             CheckStep::CheckRetv => {
                 // Get return value of the command from test target and compare with model.
                 let test_retv = self.port.receive_retv();
-                self.printer.print(&format!(
-                    "Expected: {:#x}, Got: {:#x}",
-                    self.retv, test_retv
-                ));
-                if retv_level != CheckLevel::None && test_retv != self.retv {
-                    self.printer.print("\x1b[1;31mReturn value mismatch\x1b[0m");
-                    self.printer.print("State:");
-                    self.printer.print(&format!("{:?}", self.state));
+                self.test_retv = test_retv;
+                // A probe is a no-op on the model, so the target return value
+                // is not checked.
+                let retv_level = if self.probe {
+                    CheckLevel::None
+                } else {
+                    retv_level
+                };
+                let expected = match &self.acceptable_retvs {
+                    Some(retvs) => format!(
+                        "one of [{}]",
+                        retvs
+                            .iter()
+                            .map(|&retv| self.retv_kind.format(retv))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    None => self.retv_kind.format(self.retv),
+                };
+                let got = self.retv_kind.format(test_retv);
+                if self.probe {
+                    self.printer.print(&format!("Got: {}", got));
+                } else {
+                    self.printer
+                        .print(&format!("Expected: {}, Got: {}", expected, got));
+                }
+                let retv_matches = match &self.acceptable_retvs {
+                    Some(retvs) => retvs.contains(&test_retv),
+                    None => test_retv == self.retv,
+                };
+                self.retv_matched = match retv_level {
+                    CheckLevel::None => None,
+                    _ => Some(retv_matches),
+                };
+                if retv_level != CheckLevel::None && !retv_matches {
+                    self.stats.record_retv_mismatch(self.round);
+                    self.printer
+                        .print(&self.color.paint("1;31", "Return value mismatch"));
+                    let state = self.state_repr(&self.state);
+                    self.print_state("State", &state);
+                    self.printer.event(&Event::Mismatch {
+                        round: self.round,
+                        kind: MismatchKind::Retv,
+                        expected: &expected,
+                        got: &got,
+                        time: self.now(),
+                    });
+                    self.printer.flush();
                     if retv_level == CheckLevel::Strict {
-                        return Err(Error::ReturnValueMismatch);
+                        self.round_checked(None)?;
+                        self.stats.finish_round();
+                        self.step = CheckStep::Command;
+                        return Err(self.mismatch_error(Error::ReturnValueMismatch));
                     }
                 }
                 // Start retrieving state from target.
@@ -140,24 +425,1573 @@ where
             CheckStep::CheckState => {
                 // Finish state retrieval, compare with model.
                 let test_state = self.port.finish_state_retrieval()?;
-                if state_level != CheckLevel::None && !test_state.matches(&self.state) {
-                    self.printer.print("\x1b[1;31mState mismatch\x1b[0m");
-                    self.printer.print("Expected:");
-                    self.printer.print(&format!("{:?}", self.state));
-                    self.printer.print("Got:");
+                if self.probe {
+                    self.printer.print("Probed state:");
                     self.printer.print(&format!("{:?}", test_state));
-                    if state_level == CheckLevel::Strict {
-                        return Err(Error::StateMismatch);
+                }
+                let mismatch = match state_level {
+                    CheckLevel::None => None,
+                    _ => test_state
+                        .mismatch_level(&self.state)
+                        .map(|level| level.min(state_level)),
+                };
+                let state_matched = match state_level {
+                    CheckLevel::None => None,
+                    _ => Some(mismatch.is_none()),
+                };
+                self.round_checked(state_matched)?;
+                if let Some(level) = mismatch {
+                    self.stats.record_state_mismatch(self.round);
+                    self.printer
+                        .print(&self.color.paint("1;31", "State mismatch"));
+                    let expected = format!("{:?}", self.state);
+                    let got = format!("{:?}", test_state);
+                    // Prefer the differing paths, dump both states otherwise.
+                    let diffs = test_state.diff(&self.state);
+                    if diffs.is_empty() {
+                        let expected = self.state_repr(&self.state);
+                        let got = self.state_repr(&test_state);
+                        self.print_state("Expected", &expected);
+                        self.print_state("Got", &got);
+                    } else {
+                        for diff in diffs {
+                            self.printer.print(&format!("state{}", diff));
+                        }
+                    }
+                    self.printer.event(&Event::Mismatch {
+                        round: self.round,
+                        kind: MismatchKind::State,
+                        expected: &expected,
+                        got: &got,
+                        time: self.now(),
+                    });
+                    self.printer.flush();
+                    if level == CheckLevel::Strict {
+                        self.state.observe(&test_state);
+                        self.stats.finish_round();
+                        self.step = CheckStep::Command;
+                        return Err(self.mismatch_error(Error::StateMismatch));
                     }
                 }
+                self.state.observe(&test_state);
+                self.stats.finish_round();
                 self.step = CheckStep::Command;
+                if self.too_many_mismatches() {
+                    return Err(Error::TooManyMismatches);
+                }
+                if let Some(every) = self.diagnostics_every {
+                    if self.round.is_multiple_of(every) {
+                        self.print_diagnostics();
+                    }
+                }
             }
+            CheckStep::Finished => {}
+        }
+        Ok(())
+    }
+
+    /// Run `rounds` more rounds, initializing the model first if needed.
+    ///
+    /// Stops early if the commander finishes, and returns the first error,
+    /// e.g. a strict mismatch. The checker can be stepped on afterwards.
+    pub fn run(
+        &mut self,
+        rounds: usize,
+        retv_level: CheckLevel,
+        state_level: CheckLevel,
+    ) -> Result<(), Error> {
+        let target = self.stats.rounds.saturating_add(rounds);
+        while self.stats.rounds < target && !self.is_finished() {
+            self.step(retv_level, state_level)?;
         }
         Ok(())
     }
 
+    /// Check if the commander has finished.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.step, CheckStep::Finished)
+    }
+
     /// Get a reference to the state.
     pub fn state(&self) -> &S {
         &self.state
     }
+
+    /// Snapshot the model state, e.g. before trying a command in a search.
+    pub fn checkpoint(&self) -> S
+    where
+        S: Clone,
+    {
+        self.state.clone()
+    }
+
+    /// Restore the model state from a snapshot.
+    ///
+    /// Only the model state is restored. The round counter, statistics and
+    /// current step are left unchanged, and the target is not rolled back;
+    /// restore between rounds, when the checker is about to get a command.
+    pub fn restore(&mut self, snapshot: S) {
+        self.state = snapshot;
+    }
+
+    /// Get a mutable reference to the printer.
+    pub fn printer_mut(&mut self) -> &mut P {
+        &mut self.printer
+    }
+
+    /// Get the checking statistics.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Get the number of the current round, 0 before the first command.
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Get the rounds in which mismatches happened, e.g. to assert on a
+    /// relaxed run.
+    pub fn mismatch_report(&self) -> MismatchReport {
+        self.stats.mismatch_report()
+    }
+
+    /// Get the per-command statistics, keyed by command name.
+    pub fn coverage(&self) -> &BTreeMap<String, CommandStats> {
+        &self.stats.commands
+    }
+
+    /// Check that every executed command kind and every kind in `expected`
+    /// ran at least `min` times.
+    ///
+    /// Prints the under-covered commands and returns `InsufficientCoverage`
+    /// otherwise. Kinds are named as in `coverage`, by the leading identifier
+    /// of their `Debug` output, so `expected` may be e.g.
+    /// `RandomCommander::names` if generators are named after their commands.
+    pub fn assert_min_coverage(&mut self, expected: &[&str], min: usize) -> Result<(), Error> {
+        let under = self.stats.under_covered(expected, min);
+        if under.is_empty() {
+            return Ok(());
+        }
+        let msg = format!("Insufficient coverage (< {}): {}", min, under.join(", "));
+        self.printer.print(&self.color.paint("1;31", &msg));
+        Err(Error::InsufficientCoverage)
+    }
+
+    /// Whether the recorded mismatches exceed the configured maximum.
+    fn too_many_mismatches(&self) -> bool {
+        let mismatches = self.stats.retv_mismatches + self.stats.state_mismatches;
+        self.max_mismatches.is_some_and(|max| mismatches > max)
+    }
+
+    /// `TooManyMismatches` once the limit is exceeded, else `error`.
+    fn mismatch_error(&self, error: Error) -> Error {
+        if self.too_many_mismatches() {
+            Error::TooManyMismatches
+        } else {
+            error
+        }
+    }
+
+    /// Current timestamp of the configured clock, if any.
+    fn now(&self) -> Option<f64> {
+        #[cfg(feature = "std")]
+        {
+            let start = self.start_time?;
+            self.clock.map(|clock| clock.now(start))
+        }
+        #[cfg(not(feature = "std"))]
+        None
+    }
+
+    /// Report the checks of the current round to the trace and hook.
+    fn round_checked(&mut self, state_matched: Option<bool>) -> Result<(), Error> {
+        if let Some(hook) = &mut self.after_check {
+            let outcome = CheckOutcome {
+                retv_matched: self.retv_matched,
+                state_matched,
+            };
+            hook(self.round, outcome);
+        }
+        #[cfg(feature = "std")]
+        let time = match self.now() {
+            Some(time) => format!(r#","time":{:.6}"#, time),
+            None => String::new(),
+        };
+        #[cfg(feature = "std")]
+        if let Some(trace) = &mut self.trace {
+            let state_matched = match state_matched {
+                Some(true) => "true",
+                Some(false) => "false",
+                None => "null",
+            };
+            writeln!(
+                trace,
+                r#"{{"round":{},"command":"{}","retv":{},"target_retv":{},"state_matched":{}{}}}"#,
+                self.round,
+                escape_json(&self.replay_key),
+                self.retv,
+                self.test_retv,
+                state_matched,
+                time
+            )
+            .map_err(|_| Error::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Print a target fault along with the command that likely caused it.
+    fn report_fault(&mut self, fault: &str) {
+        let headline = if self.round == 0 {
+            String::from("[ Target fault before the first command ]")
+        } else {
+            format!("[ Target fault in round {} ]", self.round)
+        };
+        self.print_summary(&self.color.paint("1;31", &headline));
+        if self.round != 0 {
+            self.printer
+                .print(&format!("Last command: {}", self.description));
+        }
+        self.printer.print(&format!("Fault: {}", fault));
+        self.printer.flush();
+    }
+
+    /// Format a state for `print_state`, pretty-printed in delta mode.
+    fn state_repr(&self, state: &S) -> String {
+        if self.delta_output {
+            format!("{:#?}", state)
+        } else {
+            format!("{:?}", state)
+        }
+    }
+
+    /// Print a labeled state, or only its lines that differ from the model
+    /// state before the last command in delta mode.
+    fn print_state(&mut self, label: &str, state: &str) {
+        if !self.delta_output {
+            self.printer.print(&format!("{}:", label));
+            self.printer.print(state);
+            return;
+        }
+        self.printer
+            .print(&format!("{} (changes since last round):", label));
+        for line in line_delta(&self.last_state, state) {
+            self.printer.print(&line);
+        }
+    }
+
+    /// Print a headline followed by the overall statistics.
+    fn print_summary(&mut self, headline: &str) {
+        self.printer.print(headline);
+        self.printer.print(&format!(
+            "rounds: {}, failed rounds: {}, retv mismatches: {}, state mismatches: {}",
+            self.stats.rounds,
+            self.stats.failed_rounds,
+            self.stats.retv_mismatches,
+            self.stats.state_mismatches
+        ));
+    }
+
+    /// Print rounds/sec, current streak and mismatch rate.
+    fn print_diagnostics(&mut self) {
+        // Without `std` there is no clock, the speed is reported as zero.
+        #[cfg(feature = "std")]
+        let elapsed = self
+            .start_time
+            .map_or(0.0, |start| start.elapsed().as_secs_f64());
+        #[cfg(not(feature = "std"))]
+        let elapsed = 0.0;
+        let speed = if elapsed > 0.0 {
+            self.stats.rounds as f64 / elapsed
+        } else {
+            0.0
+        };
+        self.printer.print(&format!(
+            "[ Diagnostics ] rounds: {}, rounds/s: {:.2}, streak: {}, mismatch rate: {:.2}%",
+            self.stats.rounds,
+            speed,
+            self.stats.streak,
+            self.stats.mismatch_rate() * 100.0
+        ));
+        self.printer.event(&Event::Diagnostics {
+            rounds: self.stats.rounds,
+            rounds_per_sec: speed,
+            streak: self.stats.streak,
+            mismatch_rate: self.stats.mismatch_rate(),
+            time: self.now(),
+        });
+    }
+}
+
+/// Builder of a `Checker`, naming the components instead of passing them
+/// to `Checker::new` by position.
+///
+/// Options left unset keep the defaults of `Checker::new`.
+pub struct CheckerBuilder<C, T, P, S> {
+    commander: Option<C>,
+    port: Option<T>,
+    printer: Option<P>,
+    state: Option<S>,
+    color: ColorMode,
+    delta_output: bool,
+    diagnostics_every: Option<usize>,
+    max_mismatches: Option<usize>,
+    #[cfg(feature = "std")]
+    trace: Option<Box<dyn Write>>,
+    #[cfg(feature = "std")]
+    clock: Option<Clock>,
+    before_command: Option<RoundHook>,
+    after_command: Option<RoundHook>,
+    after_check: Option<CheckHook>,
+}
+
+impl<C, T, P, S> CheckerBuilder<C, T, P, S>
+where
+    C: Commander<S>,
+    T: TestPort<S>,
+    P: Printer,
+    S: AbstractState + Debug,
+{
+    pub fn new() -> Self {
+        Self {
+            commander: None,
+            port: None,
+            printer: None,
+            state: None,
+            color: ColorMode::Always,
+            delta_output: false,
+            diagnostics_every: None,
+            max_mismatches: None,
+            #[cfg(feature = "std")]
+            trace: None,
+            #[cfg(feature = "std")]
+            clock: None,
+            before_command: None,
+            after_command: None,
+            after_check: None,
+        }
+    }
+    /// Set the generator of commands.
+    pub fn commander(mut self, commander: C) -> Self {
+        self.commander = Some(commander);
+        self
+    }
+    /// Set the port to communicate with the target.
+    pub fn port(mut self, port: T) -> Self {
+        self.port = Some(port);
+        self
+    }
+    /// Set the info printer.
+    pub fn printer(mut self, printer: P) -> Self {
+        self.printer = Some(printer);
+        self
+    }
+    /// Set the initial abstract state of the model.
+    pub fn state(mut self, state: S) -> Self {
+        self.state = Some(state);
+        self
+    }
+    /// See `Checker::set_color_mode`.
+    pub fn color_mode(mut self, color: ColorMode) -> Self {
+        self.color = color;
+        self
+    }
+    /// See `Checker::set_delta_output`.
+    pub fn delta_output(mut self, enabled: bool) -> Self {
+        self.delta_output = enabled;
+        self
+    }
+    /// See `Checker::set_diagnostics_every`.
+    pub fn diagnostics_every(mut self, every: Option<usize>) -> Self {
+        self.diagnostics_every = every;
+        self
+    }
+    /// See `Checker::set_max_mismatches`.
+    pub fn max_mismatches(mut self, max: Option<usize>) -> Self {
+        self.max_mismatches = max;
+        self
+    }
+    /// See `Checker::set_trace_sink`.
+    #[cfg(feature = "std")]
+    pub fn trace_sink(mut self, sink: Box<dyn Write>) -> Self {
+        self.trace = Some(sink);
+        self
+    }
+    /// See `Checker::set_clock`.
+    #[cfg(feature = "std")]
+    pub fn clock(mut self, clock: Option<Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    /// See `Checker::on_before_command`.
+    pub fn on_before_command(mut self, f: Box<dyn FnMut(usize)>) -> Self {
+        self.before_command = Some(f);
+        self
+    }
+    /// See `Checker::on_after_command`.
+    pub fn on_after_command(mut self, f: Box<dyn FnMut(usize)>) -> Self {
+        self.after_command = Some(f);
+        self
+    }
+    /// See `Checker::on_after_check`.
+    pub fn on_after_check(mut self, f: Box<dyn FnMut(usize, CheckOutcome)>) -> Self {
+        self.after_check = Some(f);
+        self
+    }
+    /// Build the checker.
+    ///
+    /// Fails with `InvalidConfig` if the commander, port, printer or state is
+    /// not set, or if diagnostics are requested every 0 rounds.
+    pub fn build(self) -> Result<Checker<C, T, P, S>, Error> {
+        if self.diagnostics_every == Some(0) {
+            return Err(Error::InvalidConfig("diagnostics every 0 rounds"));
+        }
+        let mut checker = Checker::new(
+            self.commander
+                .ok_or(Error::InvalidConfig("commander not set"))?,
+            self.port.ok_or(Error::InvalidConfig("port not set"))?,
+            self.printer
+                .ok_or(Error::InvalidConfig("printer not set"))?,
+            self.state.ok_or(Error::InvalidConfig("state not set"))?,
+        );
+        checker.color = self.color;
+        checker.delta_output = self.delta_output;
+        checker.diagnostics_every = self.diagnostics_every;
+        checker.max_mismatches = self.max_mismatches;
+        #[cfg(feature = "std")]
+        {
+            checker.trace = self.trace;
+            checker.clock = self.clock;
+        }
+        checker.before_command = self.before_command;
+        checker.after_command = self.after_command;
+        checker.after_check = self.after_check;
+        Ok(checker)
+    }
+}
+
+impl<C, T, P, S> Default for CheckerBuilder<C, T, P, S>
+where
+    C: Commander<S>,
+    T: TestPort<S>,
+    P: Printer,
+    S: AbstractState + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Largest table `line_delta` builds to pair up the changed lines.
+const MAX_DELTA_CELLS: usize = 1 << 16;
+
+/// Diff two texts line by line, returning removed lines prefixed with `- `
+/// and added lines prefixed with `+ `, in order.
+///
+/// Lines shared at the start and end are skipped first. If the rest is too
+/// large to pair up within `MAX_DELTA_CELLS`, it is reported as all removed
+/// and all added.
+fn line_delta(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+    let removed = old.iter().map(|line| format!("- {}", line));
+    let added = new.iter().map(|line| format!("+ {}", line));
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_DELTA_CELLS {
+        return removed.chain(added).collect();
+    }
+    // lcs[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut delta = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            delta.push(format!("- {}", old[i]));
+            i += 1;
+        } else {
+            delta.push(format!("+ {}", new[j]));
+            j += 1;
+        }
+    }
+    delta
+}
+
+/// Run the model against itself for `rounds` rounds and return the final state.
+///
+/// The target is emulated by a `MockTestPort` holding a copy of `initial`, so
+/// every check is expected to pass. Stops early if the commander finishes.
+/// Useful to try out a model before a real target is available.
+pub fn simulate<C, P, S>(initial: S, commander: C, rounds: usize, printer: P) -> Result<S, Error>
+where
+    C: Commander<S>,
+    P: Printer,
+    S: AbstractState + Debug + Clone,
+{
+    let port = MockTestPort::new(initial.clone());
+    let mut checker = Checker::new(commander, port, printer, initial);
+    checker.run(rounds, CheckLevel::Strict, CheckLevel::Strict)?;
+    Ok(checker.state().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::testing::SharedBytes;
+    use crate::testing::{
+        counter_target, generators, replay, Add, NullPrinter, Reset, SharedLines,
+    };
+    use crate::{
+        ClosureTestPort, Command, CommandChannel, JsonPrinter, Probe, RandomCommander,
+        RecordingTestPort, ReplayCommander, RingBufferPrinter, StateChannel, TruncatingPrinter,
+    };
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    /// Checker of `commands` on a counter against a mock target, with
+    /// uncolored output collected in lines.
+    fn checker<const N: usize>(
+        commands: [Box<dyn Command<u32>>; N],
+    ) -> Checker<ReplayCommander<u32>, MockTestPort<u32>, Vec<String>, u32> {
+        let mut checker = Checker::new(replay(commands), MockTestPort::new(0), Vec::new(), 0);
+        checker.set_color_mode(ColorMode::Never);
+        checker
+    }
+
+    #[test]
+    fn coverage_counts_expected_commands_that_never_ran() {
+        let mut checker = checker([
+            Box::new(Add(1)),
+            Box::new(Add(2)),
+            Box::new(Reset),
+            Box::new(Reset),
+        ]);
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert_eq!(
+            checker.assert_min_coverage(&["Add", "Reset", "Close"], 2),
+            Err(Error::InsufficientCoverage)
+        );
+        assert_eq!(
+            checker.printer.last().map(String::as_str),
+            Some("Insufficient coverage (< 2): Close")
+        );
+        assert_eq!(
+            checker.assert_min_coverage(&[], 3),
+            Err(Error::InsufficientCoverage)
+        );
+        assert_eq!(
+            checker.printer.last().map(String::as_str),
+            Some("Insufficient coverage (< 3): Add, Reset")
+        );
+        assert_eq!(checker.assert_min_coverage(&["Add", "Reset"], 2), Ok(()));
+    }
+
+    /// Target faulting on the command numbered `fault_at`, counting from 1.
+    struct FaultyPort {
+        inner: MockTestPort<u32>,
+        sent: usize,
+        fault_at: usize,
+    }
+
+    impl CommandChannel<u32> for FaultyPort {
+        fn send_command(&mut self, command: &dyn Command<u32>) -> Result<(), Error> {
+            self.sent += 1;
+            if self.sent == self.fault_at {
+                return Err(Error::TargetFault(String::from("kernel panic: null deref")));
+            }
+            self.inner.send_command(command)
+        }
+        fn receive_retv(&mut self) -> isize {
+            self.inner.receive_retv()
+        }
+    }
+
+    impl StateChannel<u32> for FaultyPort {
+        fn start_state_retrieval(&mut self) -> Result<(), Error> {
+            self.inner.start_state_retrieval()
+        }
+        fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+            self.inner.retrieve_state_data()
+        }
+        fn finish_state_retrieval(&mut self) -> Result<u32, Error> {
+            self.inner.finish_state_retrieval()
+        }
+    }
+
+    impl TestPort<u32> for FaultyPort {}
+
+    #[test]
+    fn target_fault_reports_the_faulting_command() {
+        let port = FaultyPort {
+            inner: MockTestPort::new(0),
+            sent: 0,
+            fault_at: 3,
+        };
+        let commands: [Box<dyn Command<u32>>; 4] = [
+            Box::new(Add(1)),
+            Box::new(Add(2)),
+            Box::new(Add(3)),
+            Box::new(Add(4)),
+        ];
+        let mut checker = Checker::new(replay(commands), port, Vec::new(), 0);
+        checker.set_color_mode(ColorMode::Never);
+        let result = checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict);
+        assert_eq!(
+            result,
+            Err(Error::TargetFault(String::from("kernel panic: null deref")))
+        );
+        assert!(checker.is_finished());
+        let lines = &checker.printer;
+        assert!(lines
+            .iter()
+            .any(|line| line == "[ Target fault in round 3 ]"));
+        assert!(lines.iter().any(|line| line == "Last command: Add(3)"));
+        assert_eq!(
+            lines.last().map(String::as_str),
+            Some("Fault: kernel panic: null deref")
+        );
+    }
+
+    /// Command setting an entry of a map, returning 0.
+    #[derive(Debug)]
+    struct Put(&'static str, u32);
+
+    impl Command<BTreeMap<&'static str, u32>> for Put {
+        fn execute(&self, state: &mut BTreeMap<&'static str, u32>) -> isize {
+            state.insert(self.0, self.1);
+            0
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn delta_output_prints_changed_lines_only() {
+        let initial = BTreeMap::from([("fd", 3), ("len", 0)]);
+        let target = initial.clone();
+        let port = ClosureTestPort::new(
+            |_: &dyn Command<BTreeMap<&'static str, u32>>| -1,
+            move || target.clone(),
+        );
+        let commander = ReplayCommander::new(vec![Box::new(Put("len", 5)) as Box<_>]);
+        let mut checker = Checker::new(commander, port, Vec::new(), initial);
+        checker.set_color_mode(ColorMode::Never);
+        checker.set_delta_output(true);
+        let result = checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict);
+        assert_eq!(result, Err(Error::ReturnValueMismatch));
+        let lines = &checker.printer;
+        let header = lines
+            .iter()
+            .position(|line| line == "State (changes since last round):")
+            .unwrap();
+        assert_eq!(
+            lines[header + 1..header + 3],
+            ["-     \"len\": 0,", "+     \"len\": 5,"]
+        );
+        assert!(!lines[header..].iter().any(|line| line.contains("\"fd\"")));
+    }
+
+    #[test]
+    fn line_delta_skips_shared_lines() {
+        let old = "a\nb\nc\nd";
+        assert_eq!(line_delta(old, old), Vec::<String>::new());
+        assert_eq!(line_delta(old, "a\nx\nc\nd"), ["- b", "+ x"]);
+        assert_eq!(line_delta(old, "a\nc\nd\ne"), ["- b", "+ e"]);
+    }
+
+    #[test]
+    fn line_delta_caps_its_table() {
+        let old: String = (0..1000).map(|i| format!("{}\n", i)).collect();
+        let new: String = (0..1000).map(|i| format!("{}\n", i + 1)).collect();
+        let delta = line_delta(&format!("head\n{}tail", old), &format!("head\n{}tail", new));
+        assert_eq!(delta.len(), 2000);
+        assert_eq!(delta[0], "- 0");
+        assert_eq!(delta[1000], "+ 1");
+    }
+
+    /// Printer recording the timestamps of events.
+    struct EventTimes(Vec<Option<f64>>);
+
+    impl Printer for EventTimes {
+        fn print(&mut self, _s: &str) {}
+        fn event(&mut self, event: &Event) {
+            self.0.push(event.time());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn events_carry_non_decreasing_timestamps() {
+        let commands: [Box<dyn Command<u32>>; 3] =
+            [Box::new(Add(1)), Box::new(Reset), Box::new(Add(2))];
+        let mut checker = Checker::new(
+            replay(commands),
+            MockTestPort::new(0),
+            EventTimes(Vec::new()),
+            0,
+        );
+        checker.set_clock(Some(Clock::Monotonic));
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        let times: Vec<f64> = checker.printer.0.iter().map(|time| time.unwrap()).collect();
+        // Init, three rounds and finished.
+        assert_eq!(times.len(), 5);
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn events_carry_no_timestamps_by_default() {
+        let mut checker = Checker::new(
+            replay([Box::new(Add(1))]),
+            MockTestPort::new(0),
+            EventTimes(Vec::new()),
+            0,
+        );
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert_eq!(checker.printer.0, [None, None, None]);
+    }
+
+    /// Command mapping `len` bytes, which must be page-aligned.
+    #[derive(Debug)]
+    struct Mmap {
+        len: u32,
+    }
+
+    impl Command<u32> for Mmap {
+        fn execute(&self, state: &mut u32) -> isize {
+            *state += self.len;
+            0
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            self.len.to_le_bytes().to_vec()
+        }
+        fn validate(&self) -> Result<(), String> {
+            if self.len.is_multiple_of(4096) {
+                Ok(())
+            } else {
+                Err(format!("len {:#x} is not page-aligned", self.len))
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_command_is_rejected_before_the_round() {
+        let mut checker = checker([
+            Box::new(Mmap { len: 0x2000 }),
+            Box::new(Mmap { len: 0x1234 }),
+        ]);
+        let result = checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict);
+        assert_eq!(result, Err(Error::InvalidCommand));
+        assert_eq!(
+            checker.printer.last().map(String::as_str),
+            Some("Invalid command Mmap { len: 4660 }: len 0x1234 is not page-aligned")
+        );
+        assert_eq!(checker.round, 1);
+        assert_eq!(*checker.state(), 0x2000);
+        assert_eq!(checker.coverage()["Mmap"].count, 1);
+    }
+
+    #[test]
+    fn probe_prints_target_state_without_checking_retv() {
+        let target = Rc::new(Cell::new(0));
+        let sent = target.clone();
+        let port = ClosureTestPort::new(
+            move |command: &dyn Command<u32>| {
+                // The target answers the no-op of a probe arbitrarily.
+                if command.is_probe() {
+                    return 42;
+                }
+                let mut state = sent.get();
+                let retv = command.execute(&mut state);
+                sent.set(state);
+                retv
+            },
+            move || target.get(),
+        );
+        let commands: [Box<dyn Command<u32>>; 3] = [
+            Box::new(Add(1)),
+            Box::new(Probe::new(vec![0])),
+            Box::new(Add(2)),
+        ];
+        let mut checker = Checker::new(replay(commands), port, Vec::new(), 0);
+        checker.set_color_mode(ColorMode::Never);
+        let result = checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict);
+        assert_eq!(result, Ok(()));
+        assert_eq!(*checker.state(), 3);
+        let lines = &checker.printer;
+        let probe = lines.iter().position(|line| line == "[ Round 2 ]").unwrap();
+        assert_eq!(
+            lines[probe..probe + 5],
+            [
+                "[ Round 2 ]",
+                "Command: Probe",
+                "Got: 0x2a",
+                "Probed state:",
+                "1"
+            ]
+        );
+        assert_eq!(checker.coverage()["Probe"].count, 1);
+    }
+
+    /// Command mapping memory at the top of the address space, returning
+    /// the address.
+    #[derive(Debug)]
+    struct MapHigh;
+
+    impl Command<u32> for MapHigh {
+        fn execute(&self, _state: &mut u32) -> isize {
+            (usize::MAX - 0xfff) as isize
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+        fn retv_kind(&self) -> RetvKind {
+            RetvKind::Unsigned
+        }
+    }
+
+    #[test]
+    fn large_unsigned_retv_is_compared_and_printed_unsigned() {
+        let mut checker = checker([Box::new(MapHigh)]);
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        let expected = format!("Expected: {0:#x}, Got: {0:#x}", usize::MAX - 0xfff);
+        assert!(checker.printer.contains(&expected));
+
+        let port =
+            ClosureTestPort::new(|_: &dyn Command<u32>| (usize::MAX - 0x1fff) as isize, || 0);
+        let commander = replay([Box::new(MapHigh)]);
+        let mut checker = Checker::new(commander, port, Vec::new(), 0);
+        let result = checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict);
+        assert_eq!(result, Err(Error::ReturnValueMismatch));
+        let expected = format!(
+            "Expected: {:#x}, Got: {:#x}",
+            usize::MAX - 0xfff,
+            usize::MAX - 0x1fff
+        );
+        assert!(checker.printer.contains(&expected));
+    }
+
+    /// Command with a description differing from its `Debug` output.
+    #[derive(Debug)]
+    struct Annotated;
+
+    impl Command<u32> for Annotated {
+        fn execute(&self, _state: &mut u32) -> isize {
+            0
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+        fn describe(&self) -> String {
+            String::from("mmap 4 KiB at head")
+        }
+    }
+
+    #[test]
+    fn printer_uses_command_description() {
+        let mut checker = checker([Box::new(Annotated)]);
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert!(checker
+            .printer
+            .iter()
+            .any(|line| line == "Command: mmap 4 KiB at head"));
+        assert!(!checker
+            .printer
+            .iter()
+            .any(|line| line.contains("Annotated")));
+        assert_eq!(checker.coverage()["Annotated"].count, 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn trace_records_replay_keys_not_descriptions() {
+        let mut checker = checker([Box::new(Annotated)]);
+        let trace = SharedBytes::default();
+        checker.set_trace_sink(Box::new(trace.clone()));
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert!(trace.lines()[0].contains(r#""command":"Annotated""#));
+    }
+
+    /// Checker of `rounds` times `Add(1)` on a counter.
+    fn adding_checker(
+        rounds: usize,
+    ) -> Checker<ReplayCommander<u32>, MockTestPort<u32>, Vec<String>, u32> {
+        let commands = (0..rounds)
+            .map(|_| Box::new(Add(1)) as Box<dyn Command<u32>>)
+            .collect();
+        let mut checker = Checker::new(
+            ReplayCommander::new(commands),
+            MockTestPort::new(0),
+            Vec::new(),
+            0,
+        );
+        checker.set_color_mode(ColorMode::Never);
+        checker
+    }
+
+    /// Printer recording the fields of diagnostics events.
+    #[derive(Default)]
+    struct Diagnostics(Vec<(usize, f64, usize, f64)>);
+
+    impl Printer for Diagnostics {
+        fn print(&mut self, _s: &str) {}
+        fn event(&mut self, event: &Event) {
+            if let Event::Diagnostics {
+                rounds,
+                rounds_per_sec,
+                streak,
+                mismatch_rate,
+                ..
+            } = *event
+            {
+                self.0.push((rounds, rounds_per_sec, streak, mismatch_rate));
+            }
+        }
+    }
+
+    #[test]
+    fn diagnostics_are_emitted_periodically() {
+        let commands = (0..120)
+            .map(|_| Box::new(Add(1)) as Box<dyn Command<u32>>)
+            .collect();
+        let mut checker = Checker::new(
+            ReplayCommander::new(commands),
+            counter_target(&[60]),
+            Diagnostics::default(),
+            0,
+        );
+        checker.set_diagnostics_every(Some(50));
+        checker
+            .run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Strict)
+            .unwrap();
+        let diagnostics = &checker.printer.0;
+        assert_eq!(diagnostics.len(), 2);
+        let (rounds, _, streak, rate) = diagnostics[0];
+        assert_eq!((rounds, streak, rate), (50, 50, 0.0));
+        // Round 60 mismatched, so 40 rounds passed since.
+        let (rounds, _, streak, rate) = diagnostics[1];
+        assert_eq!((rounds, streak, rate), (100, 40, 0.01));
+        for &(_, speed, _, _) in diagnostics {
+            // Without `std` there is no clock to measure the speed.
+            assert_eq!(speed > 0.0, cfg!(feature = "std"));
+        }
+        assert_eq!(checker.stats().rounds, 120);
+    }
+
+    #[test]
+    fn simulate_returns_final_model_state() {
+        let state = simulate(0, RandomCommander::new(7, generators()), 20, NullPrinter).unwrap();
+        // Apply the same commands to the model directly.
+        let mut commander = RandomCommander::new(7, generators());
+        let mut expected = 0;
+        for _ in 0..20 {
+            match commander.command(&expected) {
+                CommanderOutcome::Command(command) => command.execute(&mut expected),
+                _ => unreachable!(),
+            };
+        }
+        assert_eq!(state, expected);
+        assert!(state > 0);
+    }
+
+    #[test]
+    fn ring_buffer_printer_writes_only_on_mismatch() {
+        let lines = SharedLines::default();
+        let printer = RingBufferPrinter::new(lines.clone(), 3);
+        let commands = (0..5)
+            .map(|_| Box::new(Add(1)) as Box<dyn Command<u32>>)
+            .collect();
+        let mut checker = Checker::new(
+            ReplayCommander::new(commands),
+            counter_target(&[3]),
+            printer,
+            0,
+        );
+        checker.set_color_mode(ColorMode::Never);
+        assert_eq!(
+            checker.run(2, CheckLevel::Strict, CheckLevel::Strict),
+            Ok(())
+        );
+        assert!(lines.lines().is_empty());
+        assert_eq!(
+            checker.run(1, CheckLevel::Strict, CheckLevel::Strict),
+            Err(Error::ReturnValueMismatch)
+        );
+        assert_eq!(lines.lines(), ["Return value mismatch", "State:", "3"]);
+    }
+
+    #[test]
+    fn categories_count_rounds_and_pass_rates() {
+        let commands: [Box<dyn Command<u32>>; 6] = [
+            Box::new(Add(1)),
+            Box::new(Add(1)),
+            Box::new(Reset),
+            Box::new(Add(2)),
+            Box::new(Reset),
+            Box::new(Add(3)),
+        ];
+        let mut checker = Checker::new(replay(commands), counter_target(&[2]), NullPrinter, 0);
+        checker
+            .run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Strict)
+            .unwrap();
+        let categories = &checker.stats().categories;
+        assert_eq!(categories.len(), 2);
+        assert_eq!(
+            (categories["arith"].count, categories["arith"].failed),
+            (4, 1)
+        );
+        assert_eq!(categories["arith"].pass_rate(), 0.75);
+        assert_eq!(
+            (categories["control"].count, categories["control"].failed),
+            (2, 0)
+        );
+        assert_eq!(categories["control"].pass_rate(), 1.0);
+    }
+
+    /// Command printing as 10 KB.
+    struct Verbose;
+
+    impl Debug for Verbose {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str(&"x".repeat(10 * 1024))
+        }
+    }
+
+    impl Command<u32> for Verbose {
+        fn execute(&self, _state: &mut u32) -> isize {
+            0
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn truncated_output_keeps_full_trace() {
+        let lines = SharedLines::default();
+        let printer = TruncatingPrinter::new(lines.clone(), 100);
+        let mut checker = Checker::new(
+            replay([Box::new(Verbose)]),
+            MockTestPort::new(0),
+            printer,
+            0,
+        );
+        checker.set_color_mode(ColorMode::Never);
+        #[cfg(feature = "std")]
+        let trace = SharedBytes::default();
+        #[cfg(feature = "std")]
+        checker.set_trace_sink(Box::new(trace.clone()));
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        let printed = format!("Command: {}... (10249 chars total)", "x".repeat(91));
+        assert!(lines.lines().contains(&printed));
+        #[cfg(feature = "std")]
+        assert!(trace.lines()[0].contains(&format!(r#""command":"{}""#, "x".repeat(10 * 1024))));
+    }
+
+    /// Commander yielding `Add(1)` `n` times, then `end`.
+    struct Limited {
+        n: usize,
+        end: Option<Error>,
+    }
+
+    impl Commander<u32> for Limited {
+        fn command(&mut self, _state: &u32) -> CommanderOutcome<u32> {
+            if self.n == 0 {
+                return match self.end.take() {
+                    Some(e) => CommanderOutcome::Error(e),
+                    None => CommanderOutcome::Finished,
+                };
+            }
+            self.n -= 1;
+            CommanderOutcome::Command(Box::new(Add(1)))
+        }
+    }
+
+    #[test]
+    fn finished_commander_stops_with_summary() {
+        let commander = Limited { n: 3, end: None };
+        let mut checker = Checker::new(commander, MockTestPort::new(0), Vec::new(), 0);
+        checker.set_color_mode(ColorMode::Never);
+        assert_eq!(
+            checker.run(10, CheckLevel::Strict, CheckLevel::Strict),
+            Ok(())
+        );
+        assert!(checker.is_finished());
+        assert_eq!(checker.stats().rounds, 3);
+        assert_eq!(
+            checker.printer[checker.printer.len() - 2..],
+            [
+                "[ Finished ]",
+                "rounds: 3, failed rounds: 0, retv mismatches: 0, state mismatches: 0"
+            ]
+        );
+        let printed = checker.printer.len();
+        assert_eq!(checker.step(CheckLevel::Strict, CheckLevel::Strict), Ok(()));
+        assert_eq!(checker.printer.len(), printed);
+    }
+
+    #[test]
+    fn failing_commander_stops_with_summary() {
+        let commander = Limited {
+            n: 2,
+            end: Some(Error::Io),
+        };
+        let mut checker = Checker::new(commander, MockTestPort::new(0), Vec::new(), 0);
+        checker.set_color_mode(ColorMode::Never);
+        assert_eq!(
+            checker.run(10, CheckLevel::Strict, CheckLevel::Strict),
+            Err(Error::Io)
+        );
+        assert!(!checker.is_finished());
+        assert_eq!(
+            checker.printer[checker.printer.len() - 2..],
+            [
+                "[ Commander failed in round 3 ]",
+                "rounds: 2, failed rounds: 0, retv mismatches: 0, state mismatches: 0"
+            ]
+        );
+    }
+
+    /// Command the target may refuse with `-EAGAIN`.
+    #[derive(Debug)]
+    struct TryLock;
+
+    impl Command<u32> for TryLock {
+        fn execute(&self, _state: &mut u32) -> isize {
+            0
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+        fn acceptable_retvs(&self, _state: &u32) -> Option<Vec<isize>> {
+            Some(vec![0, -11])
+        }
+    }
+
+    /// Run `TryLock` against a target returning `retv`.
+    fn try_lock(retv: isize) -> (Result<(), Error>, Vec<String>) {
+        let port = ClosureTestPort::new(move |_: &dyn Command<u32>| retv, || 0);
+        let mut checker = Checker::new(replay([Box::new(TryLock)]), port, Vec::new(), 0);
+        checker.set_color_mode(ColorMode::Never);
+        let result = checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict);
+        (result, checker.printer)
+    }
+
+    #[test]
+    fn any_acceptable_retv_matches() {
+        let (result, lines) = try_lock(-11);
+        assert_eq!(result, Ok(()));
+        assert!(lines.contains(&String::from("Expected: one of [0x0, -0xb], Got: -0xb")));
+    }
+
+    #[test]
+    fn unacceptable_retv_mismatches() {
+        let (result, lines) = try_lock(-22);
+        assert_eq!(result, Err(Error::ReturnValueMismatch));
+        assert!(lines.contains(&String::from("Expected: one of [0x0, -0xb], Got: -0x16")));
+    }
+
+    #[test]
+    fn recording_port_captures_commands_in_order() {
+        let mut checker = Checker::new(
+            replay([Box::new(Add(1)), Box::new(Annotated), Box::new(Add(2))]),
+            RecordingTestPort::new(0, 0),
+            NullPrinter,
+            0,
+        );
+        checker
+            .run(usize::MAX, CheckLevel::None, CheckLevel::None)
+            .unwrap();
+        assert_eq!(checker.port.commands(), ["Add(1)", "Annotated", "Add(2)"]);
+    }
+
+    #[test]
+    fn state_mismatch_prints_differing_paths_only() {
+        let initial = BTreeMap::from([("fd", 3), ("len", 0)]);
+        let target = initial.clone();
+        let port = ClosureTestPort::new(
+            |_: &dyn Command<BTreeMap<&'static str, u32>>| 0,
+            move || target.clone(),
+        );
+        let commander = ReplayCommander::new(vec![Box::new(Put("len", 5)) as Box<_>]);
+        let mut checker = Checker::new(commander, port, Vec::new(), initial);
+        checker.set_color_mode(ColorMode::Never);
+        let result = checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict);
+        assert_eq!(result, Err(Error::StateMismatch));
+        let at = checker
+            .printer
+            .iter()
+            .position(|line| line == "State mismatch")
+            .unwrap();
+        assert_eq!(checker.printer[at + 1], "state[\"len\"]: expected 5, got 0");
+        assert_eq!(checker.printer.len(), at + 2);
+    }
+
+    #[test]
+    fn run_checks_rounds_against_mock_port() {
+        let mut checker = adding_checker(150);
+        assert_eq!(
+            checker.run(100, CheckLevel::Strict, CheckLevel::Strict),
+            Ok(())
+        );
+        assert_eq!(checker.stats().rounds, 100);
+        assert_eq!(*checker.state(), 100);
+        // The checker is left between rounds, so stepping goes on from there.
+        while checker.round() == 100 {
+            checker
+                .step(CheckLevel::Strict, CheckLevel::Strict)
+                .unwrap();
+        }
+        assert_eq!(*checker.state(), 101);
+    }
+
+    #[test]
+    fn run_to_completion_after_some_rounds() {
+        let mut checker = adding_checker(5);
+        checker
+            .run(2, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert!(checker.is_finished());
+        assert_eq!(*checker.state(), 5);
+    }
+
+    #[test]
+    fn coverage_counts_each_command_kind() {
+        let mut checker = checker([
+            Box::new(Add(1)),
+            Box::new(Reset),
+            Box::new(Add(2)),
+            Box::new(Add(3)),
+        ]);
+        assert!(checker.coverage().is_empty());
+        checker
+            .run(3, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert_eq!(checker.round(), 3);
+        assert_eq!(checker.coverage()["Add"].count, 2);
+        assert_eq!(checker.coverage()["Reset"].count, 1);
+        checker
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert_eq!(checker.round(), 4);
+        let counts: Vec<_> = checker
+            .coverage()
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats.count, stats.failed))
+            .collect();
+        assert_eq!(counts, [("Add", 3, 0), ("Reset", 1, 0)]);
+    }
+
+    #[test]
+    fn restore_rolls_back_the_model_only() {
+        let mut checker = checker([Box::new(Add(2)), Box::new(Add(5))]);
+        checker
+            .run(1, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        let snapshot = checker.checkpoint();
+        checker
+            .run(1, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert_eq!(*checker.state(), 7);
+        checker.restore(snapshot);
+        assert_eq!(*checker.state(), 2);
+        assert_eq!(checker.round(), 2);
+    }
+
+    #[test]
+    fn json_printer_writes_one_event_per_line() {
+        let commands: [Box<dyn Command<u32>>; 2] = [Box::new(Add(1)), Box::new(Add(2))];
+        let printer = JsonPrinter::new(Vec::new());
+        let mut checker = Checker::new(replay(commands), counter_target(&[2]), printer, 0);
+        checker
+            .run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Strict)
+            .unwrap();
+        let lines = checker.printer.into_inner();
+        assert_eq!(
+            lines,
+            [
+                r#"{"type":"init","state":"0"}"#,
+                r#"{"type":"round","round":1,"command":"Add(1)"}"#,
+                r#"{"type":"round","round":2,"command":"Add(2)"}"#,
+                r#"{"type":"mismatch","round":2,"kind":"retv","expected":"0x3","got":"0x4"}"#,
+                r#"{"type":"finished","rounds":2,"failed_rounds":1}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn relaxed_mismatches_are_reported_by_round() {
+        let count = Rc::new(Cell::new((0, 0)));
+        let target = count.clone();
+        // Return values are off in rounds 2 and 5, states in rounds 3 and 5.
+        let port = ClosureTestPort::new(
+            move |command: &dyn Command<u32>| {
+                let (round, mut value) = target.get();
+                let retv = command.execute(&mut value);
+                target.set((round + 1, value));
+                retv + [2, 5].contains(&(round + 1)) as isize
+            },
+            move || {
+                let (round, value) = count.get();
+                value + [3, 5].contains(&round) as u32
+            },
+        );
+        let commands = (0..6)
+            .map(|_| Box::new(Add(1)) as Box<dyn Command<u32>>)
+            .collect();
+        let mut checker = Checker::new(ReplayCommander::new(commands), port, NullPrinter, 0);
+        assert!(checker.mismatch_report().is_empty());
+        checker
+            .run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Relaxed)
+            .unwrap();
+        let report = checker.mismatch_report();
+        assert_eq!(report.retv_rounds, [2, 5]);
+        assert_eq!(report.state_rounds, [3, 5]);
+        assert_eq!(report.total(), 4);
+        assert_eq!(checker.stats().failed_rounds, 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn trace_records_every_round() {
+        let commands: [Box<dyn Command<u32>>; 3] =
+            [Box::new(Add(1)), Box::new(Reset), Box::new(Add(2))];
+        let mut checker = Checker::new(replay(commands), counter_target(&[2]), NullPrinter, 0);
+        let trace = SharedBytes::default();
+        checker.set_trace_sink(Box::new(trace.clone()));
+        checker
+            .run(2, CheckLevel::Relaxed, CheckLevel::Strict)
+            .unwrap();
+        checker
+            .run(1, CheckLevel::Strict, CheckLevel::None)
+            .unwrap();
+        assert_eq!(
+            trace.lines(),
+            [
+                r#"{"round":1,"command":"Add(1)","retv":1,"target_retv":1,"state_matched":true}"#,
+                r#"{"round":2,"command":"Reset","retv":0,"target_retv":1,"state_matched":true}"#,
+                r#"{"round":3,"command":"Add(2)","retv":2,"target_retv":2,"state_matched":null}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn hooks_run_in_order_every_round() {
+        let commands: [Box<dyn Command<u32>>; 3] =
+            [Box::new(Add(1)), Box::new(Add(1)), Box::new(Reset)];
+        let mut checker = Checker::new(replay(commands), counter_target(&[2]), NullPrinter, 0);
+        let log = SharedLines::default();
+        let mut before = log.clone();
+        checker.on_before_command(Box::new(move |round| {
+            before.print(&format!("before {}", round))
+        }));
+        let mut after = log.clone();
+        checker.on_after_command(Box::new(move |round| {
+            after.print(&format!("after {}", round))
+        }));
+        let mut checked = log.clone();
+        checker.on_after_check(Box::new(move |round, outcome| {
+            checked.print(&format!("check {} {}", round, outcome.passed()))
+        }));
+        checker
+            .run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Strict)
+            .unwrap();
+        assert_eq!(
+            log.lines(),
+            [
+                "before 1",
+                "after 1",
+                "check 1 true",
+                "before 2",
+                "after 2",
+                "check 2 false",
+                "before 3",
+                "after 3",
+                "check 3 true",
+                "before 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_relaxed_mismatches_fail() {
+        let commands = (0..6)
+            .map(|_| Box::new(Add(1)) as Box<dyn Command<u32>>)
+            .collect();
+        let mut checker = Checker::new(
+            ReplayCommander::new(commands),
+            counter_target(&[1, 3, 4, 5]),
+            NullPrinter,
+            0,
+        );
+        checker.set_max_mismatches(Some(2));
+        assert_eq!(
+            checker.run(3, CheckLevel::Relaxed, CheckLevel::Relaxed),
+            Ok(())
+        );
+        assert_eq!(
+            checker.run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Relaxed),
+            Err(Error::TooManyMismatches)
+        );
+        assert_eq!(checker.round(), 4);
+        // The limit applies whatever the check levels.
+        checker.set_max_mismatches(Some(0));
+        assert_eq!(
+            checker.run(1, CheckLevel::None, CheckLevel::None),
+            Err(Error::TooManyMismatches)
+        );
+    }
+
+    #[test]
+    fn never_color_mode_prints_no_escapes() {
+        let output = |mode| {
+            let commands: [Box<dyn Command<u32>>; 2] = [Box::new(Add(1)), Box::new(Add(2))];
+            let mut checker = Checker::new(replay(commands), counter_target(&[2]), Vec::new(), 0);
+            checker.set_color_mode(mode);
+            checker
+                .run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Strict)
+                .unwrap();
+            checker.printer.concat()
+        };
+        let plain = output(ColorMode::Never);
+        assert!(plain.contains("Return value mismatch"));
+        assert!(!plain.contains('\x1b'));
+        assert!(output(ColorMode::Always).contains("\x1b[1;31mReturn value mismatch\x1b[0m"));
+    }
+
+    #[test]
+    fn builder_configures_and_runs_a_checker() {
+        let commands: [Box<dyn Command<u32>>; 4] = [
+            Box::new(Add(1)),
+            Box::new(Add(2)),
+            Box::new(Add(3)),
+            Box::new(Add(4)),
+        ];
+        let mut checker = Checker::builder()
+            .commander(replay(commands))
+            .port(counter_target(&[1, 2]))
+            .printer(Vec::new())
+            .state(0)
+            .color_mode(ColorMode::Never)
+            .max_mismatches(Some(1))
+            .build()
+            .unwrap();
+        assert_eq!(
+            checker.run(usize::MAX, CheckLevel::Relaxed, CheckLevel::Strict),
+            Err(Error::TooManyMismatches)
+        );
+        assert_eq!(checker.round(), 2);
+        assert!(checker.printer.iter().all(|line| !line.contains('\x1b')));
+    }
+
+    #[test]
+    fn builder_defaults_match_new() {
+        let mut built = Checker::builder()
+            .commander(replay([Box::new(Add(1))]))
+            .port(MockTestPort::new(0))
+            .printer(Vec::new())
+            .state(0)
+            .build()
+            .unwrap();
+        let mut new = Checker::new(
+            replay([Box::new(Add(1))]),
+            MockTestPort::new(0),
+            Vec::new(),
+            0,
+        );
+        for checker in [&mut built, &mut new] {
+            checker
+                .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+                .unwrap();
+        }
+        assert_eq!(built.printer, new.printer);
+    }
+
+    #[test]
+    fn builder_requires_a_port() {
+        let checker = Checker::<_, MockTestPort<u32>, _, _>::builder()
+            .commander(replay([Box::new(Add(1))]))
+            .printer(NullPrinter)
+            .state(0)
+            .build();
+        assert_eq!(checker.err(), Some(Error::InvalidConfig("port not set")));
+    }
+
+    #[test]
+    fn builder_rejects_zero_diagnostics_period() {
+        let checker = Checker::builder()
+            .commander(replay([Box::new(Add(1))]))
+            .port(MockTestPort::new(0))
+            .printer(NullPrinter)
+            .state(0)
+            .diagnostics_every(Some(0))
+            .build();
+        assert_eq!(
+            checker.err(),
+            Some(Error::InvalidConfig("diagnostics every 0 rounds"))
+        );
+    }
+
+    #[test]
+    fn too_many_strict_mismatches_fail() {
+        let commands = (0..6)
+            .map(|_| Box::new(Add(1)) as Box<dyn Command<u32>>)
+            .collect();
+        let mut checker = Checker::new(
+            ReplayCommander::new(commands),
+            counter_target(&[1, 3, 4, 5]),
+            NullPrinter,
+            0,
+        );
+        checker.set_max_mismatches(Some(1));
+        assert_eq!(
+            checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::None),
+            Err(Error::ReturnValueMismatch)
+        );
+        assert_eq!(checker.round(), 1);
+        // The second strict mismatch exceeds the limit, which wins.
+        assert_eq!(
+            checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::None),
+            Err(Error::TooManyMismatches)
+        );
+        assert_eq!(checker.round(), 3);
+        assert_eq!(checker.stats().retv_mismatches, 2);
+    }
 }
@@ -0,0 +1,221 @@
+use super::{Command, Commander, CommanderOutcome, RetvKind};
+use crate::AbstractState;
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::fmt::Debug;
+
+/// A command tagged with a label shown in front of its description.
+pub struct Tagged<S> {
+    tag: &'static str,
+    inner: Box<dyn Command<S>>,
+}
+
+impl<S> Tagged<S> {
+    pub fn new(tag: &'static str, inner: Box<dyn Command<S>>) -> Self {
+        Self { tag, inner }
+    }
+    /// Get the tag.
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+}
+
+impl<S> Debug for Tagged<S>
+where
+    S: AbstractState,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<S> Command<S> for Tagged<S>
+where
+    S: AbstractState,
+{
+    fn execute(&self, state: &mut S) -> isize {
+        self.inner.execute(state)
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        self.inner.to_bytes()
+    }
+    fn describe(&self) -> String {
+        format!("[{}] {}", self.tag, self.inner.describe())
+    }
+    fn category(&self) -> &'static str {
+        self.inner.category()
+    }
+    fn retv_kind(&self) -> RetvKind {
+        self.inner.retv_kind()
+    }
+    fn is_probe(&self) -> bool {
+        self.inner.is_probe()
+    }
+    fn validate(&self) -> Result<(), String> {
+        self.inner.validate()
+    }
+    fn acceptable_retvs(&self, state: &S) -> Option<Vec<isize>> {
+        self.inner.acceptable_retvs(state)
+    }
+}
+
+/// Alternate between two commanders (profiles) every `period` commands.
+///
+/// Commands are tagged with the name of the profile that produced them.
+/// Checking finishes as soon as the active commander finishes.
+pub struct AlternatingCommander<A, B> {
+    first: (&'static str, A),
+    second: (&'static str, B),
+    period: usize,
+    count: usize,
+}
+
+impl<A, B> AlternatingCommander<A, B> {
+    /// Create an alternating commander starting with `first`.
+    pub fn new(first: (&'static str, A), second: (&'static str, B), period: usize) -> Self {
+        Self {
+            first,
+            second,
+            period: period.max(1),
+            count: 0,
+        }
+    }
+    /// Whether the first profile produces the next command.
+    fn first_active(&self) -> bool {
+        (self.count / self.period).is_multiple_of(2)
+    }
+    /// Name of the profile producing the next command.
+    pub fn active_profile(&self) -> &'static str {
+        if self.first_active() {
+            self.first.0
+        } else {
+            self.second.0
+        }
+    }
+}
+
+impl<S, A, B> Commander<S> for AlternatingCommander<A, B>
+where
+    S: AbstractState + 'static,
+    A: Commander<S>,
+    B: Commander<S>,
+{
+    fn command(&mut self, state: &S) -> CommanderOutcome<S> {
+        let (tag, outcome) = if self.first_active() {
+            (self.first.0, self.first.1.command(state))
+        } else {
+            (self.second.0, self.second.1.command(state))
+        };
+        self.count += 1;
+        match outcome {
+            CommanderOutcome::Command(command) => {
+                CommanderOutcome::Command(Box::new(Tagged::new(tag, command)))
+            }
+            outcome => outcome,
+        }
+    }
+}
+
+/// Run commanders (phases) one after another, e.g. a deterministic setup
+/// followed by random exploration.
+///
+/// Moves on to the next phase once the current one finishes, and finishes
+/// when the last phase does. An unbounded last phase such as a
+/// `RandomCommander` therefore runs forever. Errors are passed on as is.
+pub struct ChainCommander<S> {
+    phases: Vec<Box<dyn Commander<S>>>,
+    current: usize,
+}
+
+impl<S> ChainCommander<S> {
+    pub fn new(phases: Vec<Box<dyn Commander<S>>>) -> Self {
+        Self { phases, current: 0 }
+    }
+    /// Index of the active phase, equal to the number of phases once all
+    /// finished.
+    pub fn phase(&self) -> usize {
+        self.current
+    }
+}
+
+impl<S> Commander<S> for ChainCommander<S>
+where
+    S: AbstractState,
+{
+    fn command(&mut self, state: &S) -> CommanderOutcome<S> {
+        while let Some(phase) = self.phases.get_mut(self.current) {
+            match phase.command(state) {
+                CommanderOutcome::Finished => self.current += 1,
+                outcome => return outcome,
+            }
+        }
+        CommanderOutcome::Finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{generators, replay, Add, Reset};
+    use crate::RandomCommander;
+    use alloc::vec;
+
+    /// Random commander using only the generator named `name`.
+    fn only(name: &str) -> RandomCommander<u32> {
+        let generators = generators()
+            .into_iter()
+            .filter(|(n, _)| n == name)
+            .collect();
+        RandomCommander::new(0, generators)
+    }
+
+    /// Description of the next command of `commander`.
+    fn next(commander: &mut impl Commander<u32>) -> String {
+        match commander.command(&0) {
+            CommanderOutcome::Command(command) => command.describe(),
+            _ => String::from("<none>"),
+        }
+    }
+
+    #[test]
+    fn alternates_profiles_every_period() {
+        let mut commander =
+            AlternatingCommander::new(("grow", only("Add")), ("shrink", only("Reset")), 10);
+        for i in 0..30 {
+            let (profile, command) = if (i / 10) % 2 == 0 {
+                ("grow", "[grow] Add(1)")
+            } else {
+                ("shrink", "[shrink] Reset")
+            };
+            assert_eq!(commander.active_profile(), profile, "round {}", i);
+            assert_eq!(next(&mut commander), command, "round {}", i);
+        }
+    }
+
+    #[test]
+    fn chains_setup_into_exploration() {
+        let setup = replay([Box::new(Add(3)), Box::new(Reset), Box::new(Add(5))]);
+        let mut commander = ChainCommander::new(vec![
+            Box::new(setup) as Box<dyn Commander<u32>>,
+            Box::new(only("Reset")),
+        ]);
+        assert_eq!(next(&mut commander), "Add(3)");
+        assert_eq!(next(&mut commander), "Reset");
+        assert_eq!(next(&mut commander), "Add(5)");
+        assert_eq!(commander.phase(), 0);
+        for _ in 0..20 {
+            assert_eq!(next(&mut commander), "Reset");
+        }
+        assert_eq!(commander.phase(), 1);
+    }
+
+    #[test]
+    fn finishes_after_the_last_phase() {
+        let mut commander = ChainCommander::new(vec![
+            Box::new(replay::<0>([])) as Box<dyn Commander<u32>>,
+            Box::new(replay([Box::new(Add(1))])),
+        ]);
+        assert_eq!(next(&mut commander), "Add(1)");
+        assert!(matches!(commander.command(&0), CommanderOutcome::Finished));
+        assert_eq!(commander.phase(), 2);
+    }
+}
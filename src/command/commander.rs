@@ -1,5 +1,16 @@
 use super::Command;
 use crate::{AbstractState, Error};
+use alloc::boxed::Box;
+
+/// Outcome of asking a commander for the next command.
+pub enum CommanderOutcome<S> {
+    /// Next command to execute.
+    Command(Box<dyn Command<S>>),
+    /// No more commands, checking finished cleanly.
+    Finished,
+    /// The commander failed.
+    Error(Error),
+}
 
 /// Generate commands for both the abstract model and the target kernel.
 pub trait Commander<S>
@@ -7,5 +18,5 @@ where
     S: AbstractState,
 {
     /// Get the next command to execute.
-    fn command(&mut self, state: &S) -> Result<Box<dyn Command<S>>, Error>;
+    fn command(&mut self, state: &S) -> CommanderOutcome<S>;
 }
@@ -0,0 +1,174 @@
+use super::{Command, Commander, CommanderOutcome};
+use crate::{AbstractState, CheckLevel, Checker, Error, Printer, TestPort};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use arbitrary::{Arbitrary, Unstructured};
+use core::fmt::Debug;
+
+/// Named constructor of commands from fuzzer input.
+pub type FuzzGenerator<S> = (
+    String,
+    Box<dyn Fn(&mut Unstructured) -> arbitrary::Result<Box<dyn Command<S>>>>,
+);
+
+/// Build a `FuzzGenerator` for a command type implementing `Arbitrary`.
+pub fn arbitrary_generator<S, T>(name: &str) -> FuzzGenerator<S>
+where
+    S: AbstractState,
+    T: for<'a> Arbitrary<'a> + Command<S> + 'static,
+{
+    (
+        String::from(name),
+        Box::new(|u: &mut Unstructured<'_>| {
+            let command: Box<dyn Command<S>> = Box::new(T::arbitrary(u)?);
+            Ok(command)
+        }),
+    )
+}
+
+/// Pick commands and their arguments from fuzzer input, e.g. for
+/// `cargo fuzz`.
+///
+/// Each command consumes bytes to choose a generator, which consumes more
+/// bytes for the arguments. The same input always yields the same commands.
+/// Finishes once the input is exhausted or no generator is registered.
+pub struct FuzzCommander<'a, S> {
+    data: Unstructured<'a>,
+    generators: Vec<FuzzGenerator<S>>,
+}
+
+impl<'a, S> FuzzCommander<'a, S> {
+    pub fn new(data: &'a [u8], generators: Vec<FuzzGenerator<S>>) -> Self {
+        Self {
+            data: Unstructured::new(data),
+            generators,
+        }
+    }
+}
+
+impl<S> Commander<S> for FuzzCommander<'_, S>
+where
+    S: AbstractState,
+{
+    fn command(&mut self, _state: &S) -> CommanderOutcome<S> {
+        if self.data.is_empty() {
+            return CommanderOutcome::Finished;
+        }
+        let Ok((_, generator)) = self.data.choose(&self.generators) else {
+            return CommanderOutcome::Finished;
+        };
+        match generator(&mut self.data) {
+            Ok(command) => CommanderOutcome::Command(command),
+            Err(_) => CommanderOutcome::Finished,
+        }
+    }
+}
+
+/// Check the commands picked from `data` until it is exhausted, with strict
+/// return value and state checks. Intended as the body of a fuzz target.
+pub fn fuzz_one<T, P, S>(
+    data: &[u8],
+    generators: Vec<FuzzGenerator<S>>,
+    port: T,
+    printer: P,
+    state: S,
+) -> Result<(), Error>
+where
+    T: TestPort<S>,
+    P: Printer,
+    S: AbstractState + Debug,
+{
+    let commander = FuzzCommander::new(data, generators);
+    let mut checker = Checker::new(commander, port, printer, state);
+    while !checker.is_finished() {
+        checker.step(CheckLevel::Strict, CheckLevel::Strict)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{NullPrinter, Reset};
+    use crate::ClosureTestPort;
+    use alloc::rc::Rc;
+    use alloc::vec;
+    use core::cell::Cell;
+
+    /// Command adding its fuzzed argument.
+    #[derive(Debug)]
+    struct Bump(u8);
+
+    impl<'a> Arbitrary<'a> for Bump {
+        fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+            Ok(Bump(u.arbitrary()?))
+        }
+    }
+
+    impl Command<u32> for Bump {
+        fn execute(&self, state: &mut u32) -> isize {
+            *state += self.0 as u32;
+            *state as isize
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+    }
+
+    fn generators() -> Vec<FuzzGenerator<u32>> {
+        vec![
+            arbitrary_generator::<u32, Bump>("Bump"),
+            (
+                String::from("Reset"),
+                Box::new(|_: &mut Unstructured<'_>| Ok(Box::new(Reset) as Box<_>)),
+            ),
+        ]
+    }
+
+    /// Descriptions of all commands picked from `data`.
+    fn picks(data: &[u8]) -> Vec<String> {
+        let mut commander = FuzzCommander::new(data, generators());
+        let mut picks = Vec::new();
+        while let CommanderOutcome::Command(command) = commander.command(&0) {
+            picks.push(command.describe());
+        }
+        picks
+    }
+
+    #[test]
+    fn same_bytes_pick_same_commands() {
+        let data = [0, 0, 0, 0, 7, 1, 0, 0, 0, 0, 0, 0, 0, 9, 1, 0, 0, 0];
+        let expected = [
+            "Bump(0)", "Bump(0)", "Reset", "Reset", "Bump(0)", "Bump(0)", "Bump(0)", "Bump(9)",
+            "Reset", "Bump(0)", "Bump(0)",
+        ];
+        assert_eq!(picks(&data), expected);
+        // Picking again from the same bytes gives the same commands.
+        assert_eq!(picks(&data), expected);
+        assert!(picks(&[]).is_empty());
+    }
+
+    #[test]
+    fn fuzz_one_checks_until_exhausted() {
+        let data = [0, 0, 0, 0, 7, 1, 0, 0, 0, 0, 0, 0, 0, 9];
+        let state = Rc::new(Cell::new(0));
+        let rounds = Rc::new(Cell::new(0));
+        let port = {
+            let (target, sent) = (state.clone(), rounds.clone());
+            let state = state.clone();
+            ClosureTestPort::new(
+                move |command: &dyn Command<u32>| {
+                    sent.set(sent.get() + 1);
+                    let mut value = target.get();
+                    let retv = command.execute(&mut value);
+                    target.set(value);
+                    retv
+                },
+                move || state.get(),
+            )
+        };
+        assert_eq!(fuzz_one(&data, generators(), port, NullPrinter, 0), Ok(()));
+        // All of Bump(0) x2, Reset x2, Bump(0) x3 and Bump(9) were checked.
+        assert_eq!(rounds.get(), 8);
+        assert_eq!(state.get(), 9);
+    }
+}
@@ -1,8 +1,26 @@
+mod combinator;
 mod commander;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod probe;
+mod random;
+mod replay;
+mod rng;
+#[cfg(feature = "std")]
+mod script;
 
 use crate::AbstractState;
-pub use commander::Commander;
+use alloc::{format, string::String, vec::Vec};
+pub use combinator::{AlternatingCommander, ChainCommander, Tagged};
+pub use commander::{Commander, CommanderOutcome};
 use core::fmt::Debug;
+#[cfg(feature = "arbitrary")]
+pub use fuzz::{arbitrary_generator, fuzz_one, FuzzCommander, FuzzGenerator};
+pub use probe::Probe;
+pub use random::{CommandGenerator, RandomCommander, WeightedCommander};
+pub use replay::ReplayCommander;
+#[cfg(feature = "std")]
+pub use script::ScriptCommander;
 
 /// A command that can be executed on a state.
 pub trait Command<T>: Debug
@@ -13,6 +31,59 @@ where
     fn execute(&self, state: &mut T) -> isize;
     /// Serialize the object to a byte array.
     fn to_bytes(&self) -> Vec<u8>;
+    /// Human-readable description of the command, used for printing.
+    ///
+    /// Defaults to the `Debug` representation.
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+    /// Category (e.g. subsystem) of the command, used for statistics.
+    fn category(&self) -> &'static str {
+        "uncategorized"
+    }
+    /// How the return value of the command is interpreted.
+    fn retv_kind(&self) -> RetvKind {
+        RetvKind::Signed
+    }
+    /// Whether the checker should print the target state after this command.
+    fn is_probe(&self) -> bool {
+        false
+    }
+    /// Statically validate the command arguments, independent of any state.
+    ///
+    /// Checked by the checker right after getting the command, so a rejected
+    /// command neither counts as a round nor reaches the model or target.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+    /// Set of return values the target may legitimately return, given the
+    /// model state after execution. `None` means the model's return value
+    /// must be matched exactly.
+    fn acceptable_retvs(&self, _state: &T) -> Option<Vec<isize>> {
+        None
+    }
+}
+
+/// Interpretation of a command's return value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetvKind {
+    /// Signed value, e.g. a negative errno. Printed in hex with a sign,
+    /// e.g. `-0xe`.
+    #[default]
+    Signed,
+    /// Unsigned value, e.g. a size or an address. Printed in hex.
+    Unsigned,
+}
+
+impl RetvKind {
+    /// Format a return value according to its kind.
+    pub fn format(&self, retv: isize) -> String {
+        match self {
+            RetvKind::Signed if retv < 0 => format!("-{:#x}", retv.unsigned_abs()),
+            RetvKind::Signed => format!("{:#x}", retv),
+            RetvKind::Unsigned => format!("{:#x}", retv as usize),
+        }
+    }
 }
 
 /// Default `to_bytes` implementation for model commands.
@@ -22,7 +93,7 @@ where
 #[macro_export]
 macro_rules! impl_to_bytes {
     () => {
-        fn to_bytes(&self) -> Vec<u8> {
+        fn to_bytes(&self) -> $crate::__private::Vec<u8> {
             let mut res = km_command::id_to_bytes(Self::ID);
             res.extend(self.0.to_bytes());
             res
@@ -102,3 +173,57 @@ macro_rules! model_command {
         }
     }
 }
+
+/// Assert that two commands commute on `state`: executing `a` then `b` must
+/// produce a state matching `b` then `a`.
+///
+/// Panics with both resulting states otherwise. Intended for model tests.
+pub fn assert_commutativity<S>(state: &S, a: &dyn Command<S>, b: &dyn Command<S>)
+where
+    S: AbstractState + Clone + Debug,
+{
+    let mut ab = state.clone();
+    a.execute(&mut ab);
+    b.execute(&mut ab);
+    let mut ba = state.clone();
+    b.execute(&mut ba);
+    a.execute(&mut ba);
+    let (a, b) = (a.describe(), b.describe());
+    assert!(
+        ab.matches(&ba),
+        "{a} and {b} do not commute\n{a} then {b}:\n{ab:?}\n{b} then {a}:\n{ba:?}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Add, Reset};
+
+    #[test]
+    fn retv_kinds_format_in_hex() {
+        assert_eq!(RetvKind::Signed.format(0x10), "0x10");
+        assert_eq!(RetvKind::Signed.format(-14), "-0xe");
+        assert_eq!(
+            RetvKind::Signed.format(isize::MIN),
+            format!("-{:#x}", isize::MIN.unsigned_abs())
+        );
+        assert_eq!(
+            RetvKind::Unsigned.format(-14),
+            format!("{:#x}", usize::MAX - 13)
+        );
+    }
+
+    #[test]
+    fn additions_commute() {
+        assert_commutativity(&5, &Add(1), &Add(2));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Add(1) and Reset do not commute\nAdd(1) then Reset:\n0\nReset then Add(1):\n1"
+    )]
+    fn addition_and_reset_do_not_commute() {
+        assert_commutativity(&5, &Add(1), &Reset);
+    }
+}
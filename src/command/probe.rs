@@ -0,0 +1,47 @@
+use super::Command;
+use crate::AbstractState;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+/// A command that does nothing on the model, inserted to fetch and print the
+/// target state between real commands.
+///
+/// The target still receives a command, so `Probe` is built from the byte
+/// encoding of a no-op command the target understands. Whatever the target
+/// returns for it is printed but not checked.
+pub struct Probe<S> {
+    bytes: Vec<u8>,
+    _marker: PhantomData<fn(&mut S)>,
+}
+
+impl<S> Probe<S> {
+    /// Create a probe sending `bytes` (a target no-op) to the target.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Debug for Probe<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Probe")
+    }
+}
+
+impl<S> Command<S> for Probe<S>
+where
+    S: AbstractState,
+{
+    fn execute(&self, _state: &mut S) -> isize {
+        0
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+    fn is_probe(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,181 @@
+use super::rng::Rng;
+use super::{Command, Commander, CommanderOutcome};
+use crate::AbstractState;
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+/// Named constructor of commands.
+pub type CommandGenerator<S> = (String, Box<dyn Fn() -> Box<dyn Command<S>>>);
+
+/// Pick commands at random among a set of generators.
+///
+/// The choice is driven by a seeded PRNG, so a run is reproduced by creating
+/// a commander with the same seed and generators. Finishes immediately if no
+/// generator is registered.
+pub struct RandomCommander<S> {
+    generators: Vec<CommandGenerator<S>>,
+    seed: u64,
+    rng: Rng,
+}
+
+impl<S> RandomCommander<S> {
+    pub fn new(seed: u64, generators: Vec<CommandGenerator<S>>) -> Self {
+        Self {
+            generators,
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+    /// Get the seed, to reproduce this run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Names of the registered generators.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.generators.iter().map(|(name, _)| name.as_str())
+    }
+}
+
+impl<S> Commander<S> for RandomCommander<S>
+where
+    S: AbstractState,
+{
+    fn command(&mut self, _state: &S) -> CommanderOutcome<S> {
+        if self.generators.is_empty() {
+            return CommanderOutcome::Finished;
+        }
+        let index = self.rng.below(self.generators.len());
+        CommanderOutcome::Command((self.generators[index].1)())
+    }
+}
+
+/// Pick commands at random among a set of generators, in proportion to
+/// their weights.
+///
+/// Seeded like `RandomCommander`. Generators with weight zero are never
+/// picked; finishes immediately if all weights are zero.
+pub struct WeightedCommander<S> {
+    generators: Vec<(u32, CommandGenerator<S>)>,
+    total: u64,
+    seed: u64,
+    rng: Rng,
+}
+
+impl<S> WeightedCommander<S> {
+    pub fn new(seed: u64, generators: Vec<(u32, CommandGenerator<S>)>) -> Self {
+        let total = generators.iter().map(|(weight, _)| *weight as u64).sum();
+        Self {
+            generators,
+            total,
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+    /// Get the seed, to reproduce this run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Probability of picking the generator with the given name.
+    pub fn probability(&self, name: &str) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let weight: u64 = self
+            .generators
+            .iter()
+            .filter(|(_, (n, _))| n == name)
+            .map(|(weight, _)| *weight as u64)
+            .sum();
+        weight as f64 / self.total as f64
+    }
+}
+
+impl<S> Commander<S> for WeightedCommander<S>
+where
+    S: AbstractState,
+{
+    fn command(&mut self, _state: &S) -> CommanderOutcome<S> {
+        if self.total == 0 {
+            return CommanderOutcome::Finished;
+        }
+        let mut pick = self.rng.next_u64() % self.total;
+        for (weight, (_, generator)) in &self.generators {
+            let weight = *weight as u64;
+            if pick < weight {
+                return CommanderOutcome::Command(generator());
+            }
+            pick -= weight;
+        }
+        unreachable!("pick is below the total weight")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{generators, Add, Reset};
+    use alloc::vec;
+
+    fn names(commander: &mut RandomCommander<u32>, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|_| match commander.command(&0) {
+                CommanderOutcome::Command(command) => command.describe(),
+                _ => panic!("random commander finished"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_yields_same_commands() {
+        let mut a = RandomCommander::new(42, generators());
+        let mut b = RandomCommander::new(a.seed(), generators());
+        let sequence = names(&mut a, 64);
+        assert_eq!(sequence, names(&mut b, 64));
+        assert!(sequence.iter().any(|name| name == "Reset"));
+        assert!(sequence.iter().any(|name| name == "Add(1)"));
+        let mut c = RandomCommander::new(43, generators());
+        assert_ne!(sequence, names(&mut c, 64));
+    }
+
+    #[test]
+    fn finishes_without_generators() {
+        let mut commander = RandomCommander::<u32>::new(1, Vec::new());
+        assert!(matches!(commander.command(&0), CommanderOutcome::Finished));
+    }
+
+    #[test]
+    fn weighted_frequencies_follow_weights() {
+        fn generator(name: &str, command: fn() -> Box<dyn Command<u32>>) -> CommandGenerator<u32> {
+            (String::from(name), Box::new(command))
+        }
+        let mut commander = WeightedCommander::new(
+            7,
+            vec![
+                (5, generator("sched", || Box::new(Add(2)))),
+                (1, generator("exit", || Box::new(Reset))),
+                (0, generator("never", || Box::new(Add(9)))),
+            ],
+        );
+        assert_eq!(commander.probability("sched"), 5.0 / 6.0);
+        assert_eq!(commander.probability("never"), 0.0);
+        let draws = 6000;
+        let mut sched = 0;
+        for _ in 0..draws {
+            match commander.command(&0) {
+                CommanderOutcome::Command(command) => match command.describe().as_str() {
+                    "Add(2)" => sched += 1,
+                    "Reset" => {}
+                    other => panic!("picked {}", other),
+                },
+                _ => panic!("weighted commander finished"),
+            }
+        }
+        let frequency = sched as f64 / draws as f64;
+        assert!((frequency - 5.0 / 6.0).abs() < 0.02, "{}", frequency);
+    }
+
+    #[test]
+    fn finishes_with_zero_weights() {
+        let mut commander = WeightedCommander::<u32>::new(1, vec![(0, generators().remove(0))]);
+        assert!(matches!(commander.command(&0), CommanderOutcome::Finished));
+    }
+}
@@ -0,0 +1,114 @@
+use super::{Command, Commander, CommanderOutcome};
+use crate::{AbstractState, Error};
+use alloc::{boxed::Box, vec::Vec};
+
+/// Replay a recorded sequence of commands, e.g. to reproduce a failure.
+///
+/// Yields the commands in order and finishes once all were replayed.
+pub struct ReplayCommander<S> {
+    commands: alloc::vec::IntoIter<Box<dyn Command<S>>>,
+}
+
+impl<S> ReplayCommander<S>
+where
+    S: AbstractState,
+{
+    pub fn new(commands: Vec<Box<dyn Command<S>>>) -> Self {
+        Self {
+            commands: commands.into_iter(),
+        }
+    }
+    /// Rebuild the commands from their recorded `Debug` forms, e.g. as
+    /// recorded by `RecordingTestPort` or in the trace of a `Checker`.
+    ///
+    /// Returns `CommandNotFound` if `factory` does not recognize a name.
+    pub fn from_names<'a, F>(
+        names: impl IntoIterator<Item = &'a str>,
+        factory: F,
+    ) -> Result<Self, Error>
+    where
+        F: FnMut(&str) -> Option<Box<dyn Command<S>>>,
+    {
+        let commands = names
+            .into_iter()
+            .map(factory)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::CommandNotFound)?;
+        Ok(Self::new(commands))
+    }
+    /// Number of commands left to replay.
+    pub fn remaining(&self) -> usize {
+        self.commands.len()
+    }
+}
+
+impl<S> Commander<S> for ReplayCommander<S>
+where
+    S: AbstractState,
+{
+    fn command(&mut self, _state: &S) -> CommanderOutcome<S> {
+        match self.commands.next() {
+            Some(command) => CommanderOutcome::Command(command),
+            None => CommanderOutcome::Finished,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{generators, Add, NullPrinter, Reset};
+    use crate::{CheckLevel, Checker, ClosureTestPort, MockTestPort, RandomCommander};
+    use alloc::rc::Rc;
+    use alloc::{format, string::String};
+    use core::cell::{Cell, RefCell};
+
+    fn factory(name: &str) -> Option<Box<dyn Command<u32>>> {
+        match name {
+            "Add(1)" => Some(Box::new(Add(1))),
+            "Reset" => Some(Box::new(Reset)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn replaying_recorded_commands_reproduces_the_state() {
+        let names = Rc::new(RefCell::new(Vec::<String>::new()));
+        let target = Rc::new(Cell::new(0));
+        let (recorded, state) = (names.clone(), target.clone());
+        let port = ClosureTestPort::new(
+            move |command: &dyn Command<u32>| {
+                recorded.borrow_mut().push(format!("{:?}", command));
+                let mut value = state.get();
+                let retv = command.execute(&mut value);
+                state.set(value);
+                retv
+            },
+            move || target.get(),
+        );
+        let commander = RandomCommander::new(7, generators());
+        let mut checker = Checker::new(commander, port, NullPrinter, 0);
+        checker
+            .run(40, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        let expected = *checker.state();
+
+        let names = names.borrow();
+        let commander =
+            ReplayCommander::from_names(names.iter().map(String::as_str), factory).unwrap();
+        assert_eq!(commander.remaining(), 40);
+        let mut replay = Checker::new(commander, MockTestPort::new(0), NullPrinter, 0);
+        replay
+            .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert!(replay.is_finished());
+        assert_eq!(replay.stats().rounds, 40);
+        assert_eq!(*replay.state(), expected);
+    }
+
+    #[test]
+    fn unknown_names_are_not_found() {
+        let commander = ReplayCommander::from_names(["Add(1)", "Close"], factory);
+        assert_eq!(commander.err(), Some(Error::CommandNotFound));
+    }
+}
@@ -0,0 +1,108 @@
+use super::{CommandGenerator, Commander, CommanderOutcome};
+use crate::{AbstractState, Error};
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::io::BufRead;
+
+/// Read commands from a script, one command name per line.
+///
+/// Each name is looked up among the generators, as in `RandomCommander`.
+/// Blank lines and lines starting with `#` are skipped. Finishes at the end
+/// of the script; unknown names fail with `CommandNotFound`, read errors
+/// with `Io`.
+pub struct ScriptCommander<S, R> {
+    reader: R,
+    generators: Vec<CommandGenerator<S>>,
+    line: String,
+}
+
+impl<S, R> ScriptCommander<S, R>
+where
+    R: BufRead,
+{
+    pub fn new(reader: R, generators: Vec<CommandGenerator<S>>) -> Self {
+        Self {
+            reader,
+            generators,
+            line: String::new(),
+        }
+    }
+}
+
+impl<S, R> Commander<S> for ScriptCommander<S, R>
+where
+    S: AbstractState,
+    R: BufRead,
+{
+    fn command(&mut self, _state: &S) -> CommanderOutcome<S> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return CommanderOutcome::Finished,
+                Ok(_) => {}
+                Err(_) => return CommanderOutcome::Error(Error::Io),
+            }
+            let name = self.line.trim();
+            if name.is_empty() || name.starts_with('#') {
+                continue;
+            }
+            return match self.generators.iter().find(|(n, _)| n == name) {
+                Some((_, generator)) => CommanderOutcome::Command(generator()),
+                None => CommanderOutcome::Error(Error::CommandNotFound),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Add, Reset};
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use std::io::Cursor;
+
+    fn generators() -> Vec<CommandGenerator<u32>> {
+        vec![
+            (String::from("spawn"), Box::new(|| Box::new(Add(1)))),
+            (String::from("sched"), Box::new(|| Box::new(Add(2)))),
+            (String::from("exit"), Box::new(|| Box::new(Reset))),
+        ]
+    }
+
+    /// Descriptions of the commands read until the commander stops.
+    fn read(script: &str) -> (Vec<String>, Option<Error>) {
+        let mut commander = ScriptCommander::new(Cursor::new(script), generators());
+        let mut commands = Vec::new();
+        loop {
+            match commander.command(&0) {
+                CommanderOutcome::Command(command) => commands.push(command.describe()),
+                CommanderOutcome::Finished => return (commands, None),
+                CommanderOutcome::Error(err) => return (commands, Some(err)),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_one_command_per_line() {
+        assert_eq!(
+            read("spawn\nsched\nexit\n"),
+            (
+                vec![String::from("Add(1)"), "Add(2)".into(), "Reset".into()],
+                None
+            )
+        );
+        assert_eq!(
+            read("# setup\n\n  spawn  \nexit"),
+            (vec![String::from("Add(1)"), "Reset".into()], None)
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_not_found() {
+        assert_eq!(
+            read("spawn\nfork\nexit\n"),
+            (vec![String::from("Add(1)")], Some(Error::CommandNotFound))
+        );
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+use alloc::string::String;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
     /// IO Error
     Io,
@@ -6,4 +8,20 @@ pub enum Error {
     StateMismatch,
     /// Return value check failed
     ReturnValueMismatch,
+    /// State update failed, with the name of the failing field
+    UpdateFailed(&'static str),
+    /// Command failed validation
+    InvalidCommand,
+    /// Some commands were executed fewer times than required
+    InsufficientCoverage,
+    /// A command name could not be resolved to a command
+    CommandNotFound,
+    /// The target did not respond in time
+    Timeout,
+    /// More mismatches happened than the configured maximum
+    TooManyMismatches,
+    /// The target crashed or panicked, with a description of the fault
+    TargetFault(String),
+    /// The checker was misconfigured, with a description of the problem
+    InvalidConfig(&'static str),
 }
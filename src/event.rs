@@ -0,0 +1,298 @@
+use alloc::format;
+use alloc::string::String;
+
+/// Kind of a mismatch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MismatchKind {
+    /// Return value mismatch.
+    Retv,
+    /// State mismatch.
+    State,
+}
+
+impl MismatchKind {
+    /// Lowercase name, e.g. `"retv"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MismatchKind::Retv => "retv",
+            MismatchKind::State => "state",
+        }
+    }
+}
+
+/// Structured checking event, passed to `Printer::event` next to the human
+/// readable output.
+///
+/// States and return values are given as the strings the checker prints.
+/// `time` is the timestamp in seconds of the clock set with
+/// `Checker::set_clock`, if any.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event<'a> {
+    /// Model initialized from the target state.
+    Init { state: &'a str, time: Option<f64> },
+    /// A round started with the given command.
+    Round {
+        round: usize,
+        command: &'a str,
+        time: Option<f64>,
+    },
+    /// Return value or state mismatch in a round.
+    Mismatch {
+        round: usize,
+        kind: MismatchKind,
+        expected: &'a str,
+        got: &'a str,
+        time: Option<f64>,
+    },
+    /// The commander finished.
+    Finished {
+        rounds: usize,
+        failed_rounds: usize,
+        time: Option<f64>,
+    },
+    /// Periodic health report, see `Checker::set_diagnostics_every`.
+    Diagnostics {
+        rounds: usize,
+        rounds_per_sec: f64,
+        streak: usize,
+        mismatch_rate: f64,
+        time: Option<f64>,
+    },
+}
+
+impl Event<'_> {
+    /// Timestamp of the event, if any.
+    pub fn time(&self) -> Option<f64> {
+        match *self {
+            Event::Init { time, .. }
+            | Event::Round { time, .. }
+            | Event::Mismatch { time, .. }
+            | Event::Finished { time, .. }
+            | Event::Diagnostics { time, .. } => time,
+        }
+    }
+
+    /// Set the timestamp of the event.
+    pub fn set_time(&mut self, stamp: f64) {
+        match self {
+            Event::Init { time, .. }
+            | Event::Round { time, .. }
+            | Event::Mismatch { time, .. }
+            | Event::Finished { time, .. }
+            | Event::Diagnostics { time, .. } => *time = Some(stamp),
+        }
+    }
+
+    /// Encode the event as a single-line JSON object tagged with `"type"`,
+    /// with a `"time"` member if the event has a timestamp.
+    pub fn to_json(&self) -> String {
+        let fields = match self {
+            Event::Init { state, .. } => {
+                format!(r#""type":"init","state":"{}""#, escape_json(state))
+            }
+            Event::Round { round, command, .. } => format!(
+                r#""type":"round","round":{},"command":"{}""#,
+                round,
+                escape_json(command)
+            ),
+            Event::Mismatch {
+                round,
+                kind,
+                expected,
+                got,
+                ..
+            } => format!(
+                r#""type":"mismatch","round":{},"kind":"{}","expected":"{}","got":"{}""#,
+                round,
+                kind.as_str(),
+                escape_json(expected),
+                escape_json(got)
+            ),
+            Event::Finished {
+                rounds,
+                failed_rounds,
+                ..
+            } => format!(
+                r#""type":"finished","rounds":{},"failed_rounds":{}"#,
+                rounds, failed_rounds
+            ),
+            Event::Diagnostics {
+                rounds,
+                rounds_per_sec,
+                streak,
+                mismatch_rate,
+                ..
+            } => format!(
+                r#""type":"diagnostics","rounds":{},"rounds_per_sec":{:.6},"streak":{},"mismatch_rate":{:.6}"#,
+                rounds, rounds_per_sec, streak, mismatch_rate
+            ),
+        };
+        match self.time() {
+            Some(time) => format!(r#"{{{},"time":{:.6}}}"#, fields, time),
+            None => format!("{{{}}}", fields),
+        }
+    }
+}
+
+/// Escape a string for use inside a JSON string literal.
+pub(crate) fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Parse a flat JSON object of string and number members, as written by
+    /// `to_json`, into its keys and decoded values.
+    fn parse(line: &str) -> Vec<(String, String)> {
+        let body = line.strip_prefix('{').unwrap().strip_suffix('}').unwrap();
+        let mut chars = body.chars().peekable();
+        let mut members = Vec::new();
+        while chars.peek().is_some() {
+            assert_eq!(chars.next(), Some('"'));
+            let key: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            assert_eq!(chars.next(), Some(':'));
+            let mut value = String::new();
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                loop {
+                    match chars.next().unwrap() {
+                        '"' => break,
+                        '\\' => match chars.next().unwrap() {
+                            'n' => value.push('\n'),
+                            'r' => value.push('\r'),
+                            't' => value.push('\t'),
+                            'u' => {
+                                let hex: String = chars.by_ref().take(4).collect();
+                                let code = u32::from_str_radix(&hex, 16).unwrap();
+                                value.push(char::from_u32(code).unwrap());
+                            }
+                            c => value.push(c),
+                        },
+                        c => value.push(c),
+                    }
+                }
+            } else {
+                while chars.peek().is_some_and(|&c| c != ',') {
+                    value.push(chars.next().unwrap());
+                }
+            }
+            members.push((key, value));
+            if chars.peek() == Some(&',') {
+                chars.next();
+            }
+        }
+        members
+    }
+
+    /// Members of `event` after a round trip through JSON.
+    fn members(event: &Event) -> Vec<(String, String)> {
+        parse(&event.to_json())
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(k, v)| (String::from(*k), String::from(*v)))
+            .collect()
+    }
+
+    #[test]
+    fn every_event_parses_back() {
+        let state = "{\"fd\": 3,\n\t\"name\": \"a\\\\b\"}\u{1}";
+        assert_eq!(
+            members(&Event::Init { state, time: None }),
+            pairs(&[("type", "init"), ("state", state)])
+        );
+        assert_eq!(
+            members(&Event::Round {
+                round: 3,
+                command: "Open(\"a\")",
+                time: None,
+            }),
+            pairs(&[
+                ("type", "round"),
+                ("round", "3"),
+                ("command", "Open(\"a\")")
+            ])
+        );
+        assert_eq!(
+            members(&Event::Mismatch {
+                round: 4,
+                kind: MismatchKind::State,
+                expected: "1",
+                got: "2",
+                time: Some(0.25),
+            }),
+            pairs(&[
+                ("type", "mismatch"),
+                ("round", "4"),
+                ("kind", "state"),
+                ("expected", "1"),
+                ("got", "2"),
+                ("time", "0.250000"),
+            ])
+        );
+        assert_eq!(
+            members(&Event::Finished {
+                rounds: 10,
+                failed_rounds: 1,
+                time: None,
+            }),
+            pairs(&[
+                ("type", "finished"),
+                ("rounds", "10"),
+                ("failed_rounds", "1")
+            ])
+        );
+        assert_eq!(
+            members(&Event::Diagnostics {
+                rounds: 50,
+                rounds_per_sec: 12.5,
+                streak: 3,
+                mismatch_rate: 0.02,
+                time: None,
+            }),
+            pairs(&[
+                ("type", "diagnostics"),
+                ("rounds", "50"),
+                ("rounds_per_sec", "12.500000"),
+                ("streak", "3"),
+                ("mismatch_rate", "0.020000"),
+            ])
+        );
+    }
+
+    #[test]
+    fn json_carries_time_if_set() {
+        let mut event = Event::Round {
+            round: 2,
+            command: "Open(\"a\")",
+            time: None,
+        };
+        assert_eq!(
+            event.to_json(),
+            r#"{"type":"round","round":2,"command":"Open(\"a\")"}"#
+        );
+        event.set_time(1.5);
+        assert_eq!(event.time(), Some(1.5));
+        assert_eq!(
+            event.to_json(),
+            r#"{"type":"round","round":2,"command":"Open(\"a\")","time":1.500000}"#
+        );
+    }
+}
@@ -1,19 +1,56 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 mod checker;
 mod command;
 mod error;
+mod event;
 mod mem;
+mod minimize;
 mod port;
 mod printer;
+mod reference;
+mod stats;
+#[cfg(test)]
+mod testing;
 
 pub mod state;
 
-pub use checker::{CheckLevel, Checker};
-pub use command::{Command, Commander};
+pub use checker::{simulate, CheckLevel, CheckOutcome, Checker, CheckerBuilder};
+#[cfg(feature = "std")]
+pub use command::ScriptCommander;
+#[cfg(feature = "arbitrary")]
+pub use command::{arbitrary_generator, fuzz_one, FuzzCommander, FuzzGenerator};
+pub use command::{
+    assert_commutativity, AlternatingCommander, ChainCommander, Command, CommandGenerator,
+    Commander, CommanderOutcome, Probe, RandomCommander, ReplayCommander, RetvKind, Tagged,
+    WeightedCommander,
+};
 pub use error::Error;
+pub use event::{Event, MismatchKind};
 pub use mem::{ReadTargetMem, WriteTargetMem};
-pub use port::{CommandChannel, MemCommandChannel, MockTestPort, StateChannel, TestPort};
-pub use printer::{Printer, StdoutPrinter};
+pub use minimize::minimize;
+pub use port::{
+    ClosureTestPort, CommandChannel, MemCommandChannel, MemRegion, MemStateChannel, MockTestPort,
+    RecordingTestPort, RemoteTestPort, StateChannel, TestPort,
+};
+#[cfg(feature = "std")]
+pub use port::{RetryTestPort, TimeoutTestPort};
+#[cfg(feature = "std")]
+pub use printer::{Clock, StdoutPrinter, TimestampPrinter};
+pub use printer::{ColorMode, JsonPrinter, Printer, RingBufferPrinter, TruncatingPrinter};
+pub use reference::{ReferenceChecker, ReferenceRound};
 pub use state::AbstractState;
+pub use stats::{CommandStats, MismatchReport, Stats};
+
+/// Items used by macro-generated code, also available without `std`.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::{format, string::String, vec, vec::Vec};
+}
 
 #[cfg(feature = "derive")]
 pub use km_derive::*;
@@ -0,0 +1,102 @@
+use alloc::vec::Vec;
+
+/// Shrink a failing sequence (e.g. of command names) with delta debugging.
+///
+/// `fails` re-runs a sequence from scratch and returns whether it still
+/// fails, e.g. by replaying it with a fresh `Checker` and `ReplayCommander`.
+/// Chunks of the sequence are removed for as long as the failure persists,
+/// so the result still fails but removing any single element makes it pass.
+/// If `items` does not fail in the first place, it is returned unchanged.
+pub fn minimize<T, F>(mut items: Vec<T>, mut fails: F) -> Vec<T>
+where
+    T: Clone,
+    F: FnMut(&[T]) -> bool,
+{
+    if !fails(&items) {
+        return items;
+    }
+    let mut granularity = 2;
+    while items.len() >= 2 {
+        let chunk = items.len().div_ceil(granularity);
+        let mut reduced = false;
+        let mut start = 0;
+        while start < items.len() {
+            let end = (start + chunk).min(items.len());
+            let candidate: Vec<T> = items[..start]
+                .iter()
+                .chain(&items[end..])
+                .cloned()
+                .collect();
+            if fails(&candidate) {
+                items = candidate;
+                granularity = (granularity - 1).max(2);
+                reduced = true;
+                break;
+            }
+            start = end;
+        }
+        if !reduced {
+            if granularity >= items.len() {
+                break;
+            }
+            granularity = (granularity * 2).min(items.len());
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Add, NullPrinter, Reset};
+    use crate::{CheckLevel, Checker, ClosureTestPort, Command, Error, ReplayCommander};
+    use alloc::rc::Rc;
+    use alloc::{boxed::Box, vec};
+    use core::cell::Cell;
+
+    /// Replay `names` against a counter target that ignores `Reset`.
+    fn check(names: &[&str]) -> Result<(), Error> {
+        let commands = names
+            .iter()
+            .map(|&name| match name {
+                "Add" => Box::new(Add(1)) as Box<dyn Command<u32>>,
+                _ => Box::new(Reset),
+            })
+            .collect();
+        let count = Rc::new(Cell::new(0));
+        let target = count.clone();
+        let port = ClosureTestPort::new(
+            move |command: &dyn Command<u32>| {
+                if command.describe() == "Reset" {
+                    return 0;
+                }
+                let mut value = target.get();
+                let retv = command.execute(&mut value);
+                target.set(value);
+                retv
+            },
+            move || count.get(),
+        );
+        let mut checker = Checker::new(ReplayCommander::new(commands), port, NullPrinter, 0);
+        checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+    }
+
+    #[test]
+    fn shrinks_to_the_failing_commands() {
+        let mut names = vec!["Add"; 900];
+        names[500] = "Reset";
+        assert_eq!(check(&names), Err(Error::StateMismatch));
+        let minimal = minimize(names, |names| check(names).is_err());
+        assert_eq!(minimal, ["Add", "Reset"]);
+        assert_eq!(check(&minimal), Err(Error::StateMismatch));
+    }
+
+    #[test]
+    fn keeps_passing_sequences() {
+        let names = vec!["Add"; 10];
+        assert_eq!(
+            minimize(names.clone(), |names| check(names).is_err()),
+            names
+        );
+    }
+}
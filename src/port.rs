@@ -1,4 +1,13 @@
+#[cfg(feature = "std")]
+use crate::RetvKind;
 use crate::{AbstractState, Command, Error, ReadTargetMem, WriteTargetMem};
+use alloc::{format, string::String, vec, vec::Vec};
+#[cfg(feature = "std")]
+use core::{fmt::Debug, marker::PhantomData};
+#[cfg(feature = "std")]
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(feature = "std")]
+use std::time::Duration;
 
 /// Trait for sending commands and receiving results from a test target.
 pub trait CommandChannel<S>
@@ -65,6 +74,18 @@ where
 {
 }
 
+/// A `TestPort` whose target only sees what a command puts on the wire: its
+/// bytes, description, category, return value kind and probe flag.
+///
+/// Such a port never calls `Command::execute`, so it can run the commands it
+/// receives from an owned copy, as `TimeoutTestPort` does. Ports emulating
+/// the target in-process, like `MockTestPort`, are not remote.
+pub trait RemoteTestPort<S>: TestPort<S>
+where
+    S: AbstractState,
+{
+}
+
 /// A mock implementation of `TestPort` that emulates a test target using an internal state.
 pub struct MockTestPort<S> {
     state: S,
@@ -108,6 +129,471 @@ where
 
 impl<S> TestPort<S> for MockTestPort<S> where S: AbstractState + Clone {}
 
+/// A `TestPort` that records the `Debug` form of every command it receives,
+/// from which `ReplayCommander::from_names` can rebuild the commands, and
+/// always reports the same return value and state.
+pub struct RecordingTestPort<S> {
+    state: S,
+    retv: isize,
+    commands: Vec<String>,
+}
+
+impl<S> RecordingTestPort<S> {
+    /// Create a recording test port reporting `retv` and `state`.
+    pub fn new(state: S, retv: isize) -> Self {
+        Self {
+            state,
+            retv,
+            commands: Vec::new(),
+        }
+    }
+    /// Get the `Debug` forms of the received commands, in order.
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+}
+
+impl<S> CommandChannel<S> for RecordingTestPort<S>
+where
+    S: AbstractState,
+{
+    fn send_command(&mut self, command: &dyn Command<S>) -> Result<(), Error> {
+        self.commands.push(format!("{:?}", command));
+        Ok(())
+    }
+    fn receive_retv(&mut self) -> isize {
+        self.retv
+    }
+}
+
+impl<S> StateChannel<S> for RecordingTestPort<S>
+where
+    S: AbstractState + Clone,
+{
+    fn start_state_retrieval(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+    fn finish_state_retrieval(&mut self) -> Result<S, Error> {
+        Ok(self.state.clone())
+    }
+}
+
+impl<S> TestPort<S> for RecordingTestPort<S> where S: AbstractState + Clone {}
+
+impl<S> RemoteTestPort<S> for RecordingTestPort<S> where S: AbstractState + Clone {}
+
+/// A `TestPort` for in-process targets, driven by closures.
+///
+/// `send` executes a command on the target and returns its return value,
+/// `get_state` produces the current target state.
+pub struct ClosureTestPort<F, G> {
+    send: F,
+    get_state: G,
+    result: isize,
+}
+
+impl<F, G> ClosureTestPort<F, G> {
+    /// Create a closure test port.
+    pub fn new(send: F, get_state: G) -> Self {
+        Self {
+            send,
+            get_state,
+            result: 0,
+        }
+    }
+}
+
+impl<S, F, G> CommandChannel<S> for ClosureTestPort<F, G>
+where
+    S: AbstractState,
+    F: FnMut(&dyn Command<S>) -> isize,
+{
+    fn send_command(&mut self, command: &dyn Command<S>) -> Result<(), Error> {
+        self.result = (self.send)(command);
+        Ok(())
+    }
+    fn receive_retv(&mut self) -> isize {
+        self.result
+    }
+}
+
+impl<S, F, G> StateChannel<S> for ClosureTestPort<F, G>
+where
+    S: AbstractState,
+    G: FnMut() -> S,
+{
+    fn start_state_retrieval(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+    fn finish_state_retrieval(&mut self) -> Result<S, Error> {
+        Ok((self.get_state)())
+    }
+}
+
+impl<S, F, G> TestPort<S> for ClosureTestPort<F, G>
+where
+    S: AbstractState,
+    F: FnMut(&dyn Command<S>) -> isize,
+    G: FnMut() -> S,
+{
+}
+
+/// A `TestPort` wrapper that runs the inner port on a worker thread and fails
+/// any call with `Timeout` if it takes longer than `timeout`, e.g. because
+/// the target hung.
+///
+/// Commands are passed to the worker as an owned copy of their bytes,
+/// description, category, return value kind and probe flag, since the
+/// borrowed command cannot outlive a timed out call. The inner port must
+/// therefore be a `RemoteTestPort`; a port executing commands itself is
+/// rejected:
+///
+/// ```compile_fail
+/// use km_checker::{MockTestPort, TimeoutTestPort};
+/// use std::time::Duration;
+///
+/// let port = TimeoutTestPort::<u32>::new(MockTestPort::new(0), Duration::from_secs(1));
+/// ```
+///
+/// The return value is fetched along with the command, so a target hanging
+/// on either fails `send_command` with `Timeout`. After a timeout the worker
+/// is abandoned and every further call fails with `Timeout`.
+#[cfg(feature = "std")]
+pub struct TimeoutTestPort<S> {
+    requests: mpsc::Sender<Request<S>>,
+    responses: mpsc::Receiver<Response<S>>,
+    timeout: Duration,
+    hung: bool,
+    retv: isize,
+}
+
+/// Call of the inner port, sent to the worker of a `TimeoutTestPort`.
+#[cfg(feature = "std")]
+enum Request<S> {
+    SendCommand(OwnedCommand<S>),
+    ReceiveExtraData(usize),
+    StartStateRetrieval,
+    RetrieveStateData,
+    FinishStateRetrieval,
+}
+
+/// Result of a call of the inner port, sent back by the worker.
+#[cfg(feature = "std")]
+enum Response<S> {
+    Unit(Result<(), Error>),
+    Retv(Result<isize, Error>),
+    Data(Result<Vec<u8>, Error>),
+    Finished(Result<bool, Error>),
+    State(Result<S, Error>),
+}
+
+/// Owned copy of a command, passed to the worker of a `TimeoutTestPort`.
+#[cfg(feature = "std")]
+struct OwnedCommand<S> {
+    bytes: Vec<u8>,
+    description: String,
+    category: &'static str,
+    retv_kind: RetvKind,
+    probe: bool,
+    state: PhantomData<fn() -> S>,
+}
+
+#[cfg(feature = "std")]
+impl<S> Debug for OwnedCommand<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> Command<S> for OwnedCommand<S>
+where
+    S: AbstractState,
+{
+    /// Never called, since the inner port is a `RemoteTestPort`.
+    fn execute(&self, _state: &mut S) -> isize {
+        unreachable!("remote test ports do not execute commands")
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+    fn describe(&self) -> String {
+        self.description.clone()
+    }
+    fn category(&self) -> &'static str {
+        self.category
+    }
+    fn retv_kind(&self) -> RetvKind {
+        self.retv_kind
+    }
+    fn is_probe(&self) -> bool {
+        self.probe
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> TimeoutTestPort<S>
+where
+    S: AbstractState + Send + 'static,
+{
+    /// Move `inner` to a new worker thread.
+    pub fn new<T>(mut inner: T, timeout: Duration) -> Self
+    where
+        T: RemoteTestPort<S> + Send + 'static,
+    {
+        let (requests, worker_requests) = mpsc::channel();
+        let (worker_responses, responses) = mpsc::channel();
+        std::thread::spawn(move || {
+            // Stops once the port is dropped.
+            for request in worker_requests {
+                let response = match request {
+                    Request::SendCommand(command) => {
+                        Response::Retv(inner.send_command(&command).map(|()| inner.receive_retv()))
+                    }
+                    Request::ReceiveExtraData(len) => Response::Data(inner.receive_extra_data(len)),
+                    Request::StartStateRetrieval => Response::Unit(inner.start_state_retrieval()),
+                    Request::RetrieveStateData => Response::Finished(inner.retrieve_state_data()),
+                    Request::FinishStateRetrieval => {
+                        Response::State(inner.finish_state_retrieval())
+                    }
+                };
+                if worker_responses.send(response).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            requests,
+            responses,
+            timeout,
+            hung: false,
+            retv: 0,
+        }
+    }
+    /// Check if a call timed out, so the port is unusable.
+    pub fn is_hung(&self) -> bool {
+        self.hung
+    }
+    /// Run `request` on the worker, waiting at most `timeout`.
+    fn call(&mut self, request: Request<S>) -> Result<Response<S>, Error> {
+        if self.hung {
+            return Err(Error::Timeout);
+        }
+        // The worker only stops if the inner port panicked.
+        self.requests.send(request).map_err(|_| Error::Io)?;
+        match self.responses.recv_timeout(self.timeout) {
+            Ok(response) => Ok(response),
+            Err(RecvTimeoutError::Timeout) => {
+                self.hung = true;
+                Err(Error::Timeout)
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(Error::Io),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> CommandChannel<S> for TimeoutTestPort<S>
+where
+    S: AbstractState + Send + 'static,
+{
+    fn send_command(&mut self, command: &dyn Command<S>) -> Result<(), Error> {
+        let command = OwnedCommand {
+            bytes: command.to_bytes(),
+            description: command.describe(),
+            category: command.category(),
+            retv_kind: command.retv_kind(),
+            probe: command.is_probe(),
+            state: PhantomData,
+        };
+        self.retv = 0;
+        match self.call(Request::SendCommand(command))? {
+            Response::Retv(result) => {
+                self.retv = result?;
+                Ok(())
+            }
+            _ => unreachable!("worker answers in order"),
+        }
+    }
+    /// Return value fetched by the last `send_command`.
+    fn receive_retv(&mut self) -> isize {
+        self.retv
+    }
+    fn receive_extra_data(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        match self.call(Request::ReceiveExtraData(len))? {
+            Response::Data(result) => result,
+            _ => unreachable!("worker answers in order"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> StateChannel<S> for TimeoutTestPort<S>
+where
+    S: AbstractState + Send + 'static,
+{
+    fn start_state_retrieval(&mut self) -> Result<(), Error> {
+        match self.call(Request::StartStateRetrieval)? {
+            Response::Unit(result) => result,
+            _ => unreachable!("worker answers in order"),
+        }
+    }
+    fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+        match self.call(Request::RetrieveStateData)? {
+            Response::Finished(result) => result,
+            _ => unreachable!("worker answers in order"),
+        }
+    }
+    fn finish_state_retrieval(&mut self) -> Result<S, Error> {
+        match self.call(Request::FinishStateRetrieval)? {
+            Response::State(result) => result,
+            _ => unreachable!("worker answers in order"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> TestPort<S> for TimeoutTestPort<S> where S: AbstractState + Send + 'static {}
+
+#[cfg(feature = "std")]
+impl<S> RemoteTestPort<S> for TimeoutTestPort<S> where S: AbstractState + Send + 'static {}
+
+/// A `TestPort` wrapper that restarts a failed state retrieval up to
+/// `max_retries` times, sleeping `backoff` before each retry, e.g. to survive
+/// a dropped line on a flaky serial link.
+///
+/// An error of `retrieve_state_data` or `finish_state_retrieval` restarts the
+/// retrieval from `start_state_retrieval`; the error is returned once the
+/// retries of the current retrieval are used up. Commands are forwarded as is,
+/// since resending one would execute it twice.
+#[cfg(feature = "std")]
+pub struct RetryTestPort<T> {
+    inner: T,
+    max_retries: usize,
+    backoff: Duration,
+    attempts: usize,
+    retries: usize,
+}
+
+#[cfg(feature = "std")]
+impl<T> RetryTestPort<T> {
+    pub fn new(inner: T, max_retries: usize, backoff: Duration) -> Self {
+        Self {
+            inner,
+            max_retries,
+            backoff,
+            attempts: 0,
+            retries: 0,
+        }
+    }
+    /// Total number of retries so far.
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+    /// Get the inner port.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> RetryTestPort<T> {
+    /// Restart the state retrieval after `err`, or return it if no retries are left.
+    fn retry<S>(&mut self, mut err: Error) -> Result<(), Error>
+    where
+        S: AbstractState,
+        T: StateChannel<S>,
+    {
+        loop {
+            if self.attempts >= self.max_retries {
+                self.attempts = 0;
+                return Err(err);
+            }
+            self.attempts += 1;
+            self.retries += 1;
+            std::thread::sleep(self.backoff);
+            match self.inner.start_state_retrieval() {
+                Ok(()) => return Ok(()),
+                Err(e) => err = e,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S, T> CommandChannel<S> for RetryTestPort<T>
+where
+    S: AbstractState,
+    T: CommandChannel<S>,
+{
+    fn send_command(&mut self, command: &dyn Command<S>) -> Result<(), Error> {
+        self.inner.send_command(command)
+    }
+    fn receive_retv(&mut self) -> isize {
+        self.inner.receive_retv()
+    }
+    fn receive_extra_data(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        self.inner.receive_extra_data(len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S, T> StateChannel<S> for RetryTestPort<T>
+where
+    S: AbstractState,
+    T: StateChannel<S>,
+{
+    fn start_state_retrieval(&mut self) -> Result<(), Error> {
+        self.attempts = 0;
+        match self.inner.start_state_retrieval() {
+            Ok(()) => Ok(()),
+            Err(e) => self.retry(e),
+        }
+    }
+    fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+        match self.inner.retrieve_state_data() {
+            Ok(finished) => Ok(finished),
+            Err(e) => self.retry(e).map(|_| false),
+        }
+    }
+    fn finish_state_retrieval(&mut self) -> Result<S, Error> {
+        loop {
+            match self.inner.finish_state_retrieval() {
+                Ok(state) => {
+                    self.attempts = 0;
+                    return Ok(state);
+                }
+                Err(e) => {
+                    self.retry(e)?;
+                    while !self.retrieve_state_data()? {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S, T> TestPort<S> for RetryTestPort<T>
+where
+    S: AbstractState,
+    T: TestPort<S>,
+{
+}
+
+#[cfg(feature = "std")]
+impl<S, T> RemoteTestPort<S> for RetryTestPort<T>
+where
+    S: AbstractState,
+    T: RemoteTestPort<S>,
+{
+}
+
 /// Facilitates sending commands and receiving results via the target's virtual memory.
 pub struct MemCommandChannel<R, W> {
     reader: R,
@@ -157,3 +643,376 @@ where
         Ok(buf)
     }
 }
+
+/// A region of target memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemRegion {
+    /// Start address.
+    pub addr: usize,
+    /// Length in bytes.
+    pub len: usize,
+    /// Whether `addr` is a virtual address (otherwise physical).
+    pub virt: bool,
+}
+
+impl MemRegion {
+    /// A region of target virtual memory.
+    pub fn virt(addr: usize, len: usize) -> Self {
+        Self {
+            addr,
+            len,
+            virt: true,
+        }
+    }
+    /// A region of target physical memory.
+    pub fn phys(addr: usize, len: usize) -> Self {
+        Self {
+            addr,
+            len,
+            virt: false,
+        }
+    }
+}
+
+/// Retrieves state by reading a list of memory regions from the target, one
+/// region per `retrieve_state_data` call, and assembling the raw bytes into a
+/// state with a user-provided function.
+pub struct MemStateChannel<R, F> {
+    reader: R,
+    regions: Vec<MemRegion>,
+    assemble: F,
+    /// Bytes of the regions read so far.
+    data: Vec<Vec<u8>>,
+}
+
+impl<R, F> MemStateChannel<R, F>
+where
+    R: ReadTargetMem,
+{
+    /// Create a state channel reading `regions` in order. `assemble` receives
+    /// the bytes of every region in the same order.
+    pub fn new(reader: R, regions: Vec<MemRegion>, assemble: F) -> Self {
+        Self {
+            reader,
+            regions,
+            assemble,
+            data: Vec::new(),
+        }
+    }
+}
+
+impl<S, R, F> StateChannel<S> for MemStateChannel<R, F>
+where
+    S: AbstractState,
+    R: ReadTargetMem,
+    F: FnMut(&[Vec<u8>]) -> Result<S, Error>,
+{
+    fn start_state_retrieval(&mut self) -> Result<(), Error> {
+        self.data.clear();
+        Ok(())
+    }
+    fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+        if let Some(region) = self.regions.get(self.data.len()) {
+            let mut buf = vec![0u8; region.len];
+            if region.virt {
+                self.reader.read_virt(region.addr, &mut buf);
+            } else {
+                self.reader.read_phys(region.addr, &mut buf);
+            }
+            self.data.push(buf);
+        }
+        Ok(self.data.len() == self.regions.len())
+    }
+    fn finish_state_retrieval(&mut self) -> Result<S, Error> {
+        (self.assemble)(&self.data)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::testing::{generators, NullPrinter};
+    use crate::{CheckLevel, Checker, RandomCommander, ReplayCommander};
+    use alloc::boxed::Box;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+    use std::thread::sleep;
+    use std::time::Instant;
+
+    /// Command the target answers with its byte.
+    #[derive(Debug)]
+    struct Echo(u8);
+
+    impl Command<u32> for Echo {
+        fn execute(&self, _state: &mut u32) -> isize {
+            self.0 as isize
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+    }
+
+    /// Remote target echoing command bytes and reporting state 7, taking
+    /// `command` to run a command, `retv` to report its return value and
+    /// `state` to report the state.
+    struct SlowTarget {
+        command: Duration,
+        retv: Duration,
+        state: Duration,
+        last: u8,
+    }
+
+    impl CommandChannel<u32> for SlowTarget {
+        fn send_command(&mut self, command: &dyn Command<u32>) -> Result<(), Error> {
+            sleep(self.command);
+            self.last = command.to_bytes()[0];
+            Ok(())
+        }
+        fn receive_retv(&mut self) -> isize {
+            sleep(self.retv);
+            self.last as isize
+        }
+    }
+
+    impl StateChannel<u32> for SlowTarget {
+        fn start_state_retrieval(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+        fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+            Ok(true)
+        }
+        fn finish_state_retrieval(&mut self) -> Result<u32, Error> {
+            sleep(self.state);
+            Ok(7)
+        }
+    }
+
+    impl TestPort<u32> for SlowTarget {}
+
+    impl RemoteTestPort<u32> for SlowTarget {}
+
+    fn slow_target(command: Duration, retv: Duration, state: Duration) -> SlowTarget {
+        SlowTarget {
+            command,
+            retv,
+            state,
+            last: 0,
+        }
+    }
+
+    #[test]
+    fn timeout_port_forwards_calls() {
+        let mut port = TimeoutTestPort::new(
+            slow_target(Duration::ZERO, Duration::ZERO, Duration::ZERO),
+            Duration::from_secs(5),
+        );
+        assert_eq!(port.send_command(&Echo(5)), Ok(()));
+        assert_eq!(port.receive_retv(), 5);
+        assert_eq!(port.start_state_retrieval(), Ok(()));
+        assert_eq!(port.retrieve_state_data(), Ok(true));
+        assert_eq!(port.finish_state_retrieval(), Ok(7));
+        assert!(!port.is_hung());
+    }
+
+    #[test]
+    fn timeout_port_times_out_slow_state() {
+        let mut port = TimeoutTestPort::new(
+            slow_target(Duration::ZERO, Duration::ZERO, Duration::from_secs(2)),
+            Duration::from_millis(50),
+        );
+        let start = Instant::now();
+        assert_eq!(port.start_state_retrieval(), Ok(()));
+        assert_eq!(port.retrieve_state_data(), Ok(true));
+        assert_eq!(port.finish_state_retrieval(), Err(Error::Timeout));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(port.is_hung());
+        assert_eq!(port.start_state_retrieval(), Err(Error::Timeout));
+    }
+
+    #[test]
+    fn timeout_port_times_out_slow_command() {
+        let mut port = TimeoutTestPort::new(
+            slow_target(Duration::from_secs(2), Duration::ZERO, Duration::ZERO),
+            Duration::from_millis(50),
+        );
+        assert_eq!(port.send_command(&Echo(1)), Err(Error::Timeout));
+        assert_eq!(port.receive_retv(), 0);
+    }
+
+    #[test]
+    fn timeout_port_times_out_slow_retv() {
+        let mut port = TimeoutTestPort::new(
+            slow_target(Duration::ZERO, Duration::from_secs(2), Duration::ZERO),
+            Duration::from_millis(50),
+        );
+        assert_eq!(port.send_command(&Echo(0)), Err(Error::Timeout));
+        assert!(port.is_hung());
+    }
+
+    #[test]
+    fn checker_reports_hung_retv_even_if_it_would_match() {
+        // The model expects 0, the value a hung port used to report.
+        let port = TimeoutTestPort::new(
+            slow_target(Duration::ZERO, Duration::from_secs(2), Duration::ZERO),
+            Duration::from_millis(50),
+        );
+        let commands: Vec<Box<dyn Command<u32>>> = vec![Box::new(Echo(0))];
+        let mut checker = Checker::new(ReplayCommander::new(commands), port, NullPrinter, 7);
+        assert_eq!(
+            checker.run(1, CheckLevel::Strict, CheckLevel::Strict),
+            Err(Error::Timeout)
+        );
+    }
+
+    #[test]
+    fn checker_reports_hung_target() {
+        let port = TimeoutTestPort::new(
+            slow_target(Duration::from_secs(2), Duration::ZERO, Duration::ZERO),
+            Duration::from_millis(50),
+        );
+        let commands: Vec<Box<dyn Command<u32>>> = vec![Box::new(Echo(1))];
+        let mut checker = Checker::new(ReplayCommander::new(commands), port, NullPrinter, 7);
+        assert_eq!(
+            checker.run(1, CheckLevel::Strict, CheckLevel::Strict),
+            Err(Error::Timeout)
+        );
+    }
+
+    /// Mock target whose next `failures` state retrievals fail.
+    struct Flaky {
+        inner: MockTestPort<u32>,
+        failures: usize,
+    }
+
+    impl CommandChannel<u32> for Flaky {
+        fn send_command(&mut self, command: &dyn Command<u32>) -> Result<(), Error> {
+            self.inner.send_command(command)
+        }
+        fn receive_retv(&mut self) -> isize {
+            self.inner.receive_retv()
+        }
+    }
+
+    impl StateChannel<u32> for Flaky {
+        fn start_state_retrieval(&mut self) -> Result<(), Error> {
+            self.inner.start_state_retrieval()
+        }
+        fn retrieve_state_data(&mut self) -> Result<bool, Error> {
+            self.inner.retrieve_state_data()
+        }
+        fn finish_state_retrieval(&mut self) -> Result<u32, Error> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(Error::Io);
+            }
+            self.inner.finish_state_retrieval()
+        }
+    }
+
+    impl TestPort<u32> for Flaky {}
+
+    fn flaky(failures: usize, max_retries: usize) -> RetryTestPort<Flaky> {
+        let inner = Flaky {
+            inner: MockTestPort::new(0),
+            failures,
+        };
+        RetryTestPort::new(inner, max_retries, Duration::ZERO)
+    }
+
+    #[test]
+    fn retry_port_recovers_from_transient_errors() {
+        let mut port = flaky(2, 3);
+        assert_eq!(port.start_state_retrieval(), Ok(()));
+        while !port.retrieve_state_data().unwrap() {}
+        assert_eq!(port.finish_state_retrieval(), Ok(0));
+        assert_eq!(port.retries(), 2);
+
+        let commands: Vec<Box<dyn Command<u32>>> = vec![Box::new(Echo(1)), Box::new(Echo(2))];
+        let mut checker = Checker::new(ReplayCommander::new(commands), flaky(2, 3), NullPrinter, 0);
+        assert_eq!(
+            checker.run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict),
+            Ok(())
+        );
+        assert_eq!(checker.stats().rounds, 2);
+    }
+
+    #[test]
+    fn retry_port_gives_up_after_max_retries() {
+        let mut port = flaky(5, 3);
+        assert_eq!(port.start_state_retrieval(), Ok(()));
+        while !port.retrieve_state_data().unwrap() {}
+        assert_eq!(port.finish_state_retrieval(), Err(Error::Io));
+        assert_eq!(port.retries(), 3);
+    }
+
+    /// Memory backend with canned virtual and physical memory at address 0.
+    struct CannedMem {
+        virt: Vec<u8>,
+        phys: Vec<u8>,
+        reads: usize,
+    }
+
+    impl ReadTargetMem for CannedMem {
+        fn read_phys(&mut self, paddr: usize, buf: &mut [u8]) {
+            self.reads += 1;
+            buf.copy_from_slice(&self.phys[paddr..paddr + buf.len()]);
+        }
+        fn read_virt(&mut self, vaddr: usize, buf: &mut [u8]) {
+            self.reads += 1;
+            buf.copy_from_slice(&self.virt[vaddr..vaddr + buf.len()]);
+        }
+    }
+
+    #[test]
+    fn mem_state_channel_assembles_regions() {
+        let mem = CannedMem {
+            virt: vec![0, 0, 0x34, 0x12, 0, 0],
+            phys: vec![7, 8, 9],
+            reads: 0,
+        };
+        let regions = vec![MemRegion::virt(2, 2), MemRegion::phys(1, 2)];
+        let mut channel = MemStateChannel::new(mem, regions, |data: &[Vec<u8>]| {
+            let word = |bytes: &Vec<u8>| u16::from_le_bytes([bytes[0], bytes[1]]);
+            Ok((word(&data[0]), word(&data[1])))
+        });
+        let state: &mut dyn StateChannel<(u16, u16)> = &mut channel;
+        state.start_state_retrieval().unwrap();
+        assert_eq!(state.retrieve_state_data(), Ok(false));
+        assert_eq!(state.retrieve_state_data(), Ok(true));
+        assert_eq!(state.finish_state_retrieval(), Ok((0x1234, 0x0908)));
+        assert_eq!(channel.reader.reads, 2);
+    }
+
+    /// In-process kernel decoding counter commands from their bytes.
+    #[derive(Default)]
+    struct Kernel {
+        count: u32,
+    }
+
+    impl Kernel {
+        fn syscall(&mut self, bytes: &[u8]) -> isize {
+            match bytes.try_into() {
+                Ok(arg) => self.count += u32::from_le_bytes(arg),
+                Err(_) => self.count = 0,
+            }
+            self.count as isize
+        }
+    }
+
+    #[test]
+    fn closure_port_drives_in_process_kernel() {
+        let kernel = Rc::new(RefCell::new(Kernel::default()));
+        let target = kernel.clone();
+        let port = ClosureTestPort::new(
+            move |c: &dyn Command<u32>| target.borrow_mut().syscall(&c.to_bytes()),
+            move || kernel.borrow().count,
+        );
+        let commander = RandomCommander::new(3, generators());
+        let mut checker = Checker::new(commander, port, NullPrinter, 0);
+        checker
+            .run(50, CheckLevel::Strict, CheckLevel::Strict)
+            .unwrap();
+        assert_eq!(checker.stats().rounds, 50);
+        assert!(checker.mismatch_report().is_empty());
+    }
+}
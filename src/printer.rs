@@ -1,14 +1,301 @@
+use crate::Event;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
+use std::io::IsTerminal;
+#[cfg(feature = "std")]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// When to color the checker output with ANSI escapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Always color the output.
+    Always,
+    /// Never color the output, e.g. when it is written to a log file.
+    Never,
+    /// Color the output only if stdout is a terminal. Never colors without
+    /// `std`.
+    Auto,
+}
+
+impl ColorMode {
+    /// Check if escapes should be emitted.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            #[cfg(feature = "std")]
+            ColorMode::Auto => std::io::stdout().is_terminal(),
+            #[cfg(not(feature = "std"))]
+            ColorMode::Auto => false,
+        }
+    }
+
+    /// Wrap `s` in the SGR escape `code` (e.g. `1;31` for bold red), or
+    /// return it as is if coloring is disabled.
+    pub fn paint(self, code: &str, s: &str) -> String {
+        if self.enabled() {
+            format!("\x1b[{}m{}\x1b[0m", code, s)
+        } else {
+            s.to_string()
+        }
+    }
+}
+
 /// Print test info to the output.
 pub trait Printer {
     /// Print an info string to the output.
     fn print(&mut self, s: &str);
+    /// Flush buffered output, if any. Called by the checker on mismatch.
+    fn flush(&mut self) {}
+    /// Handle a structured event. Ignored by default, as the same
+    /// information is printed as text.
+    fn event(&mut self, _event: &Event) {}
 }
 
 /// Stdout printer.
+#[cfg(feature = "std")]
 pub struct StdoutPrinter;
 
+#[cfg(feature = "std")]
 impl Printer for StdoutPrinter {
     fn print(&mut self, s: &str) {
-        println!("{}", s);
+        std::println!("{}", s);
+    }
+}
+
+/// Printer that keeps the last `capacity` lines in memory and only writes them
+/// to the inner printer when flushed. Events are passed on immediately.
+pub struct RingBufferPrinter<P> {
+    inner: P,
+    capacity: usize,
+    lines: VecDeque<String>,
+}
+
+impl<P> RingBufferPrinter<P>
+where
+    P: Printer,
+{
+    /// Create a ring buffer printer keeping at most `capacity` lines.
+    pub fn new(inner: P, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            lines: VecDeque::with_capacity(capacity),
+        }
+    }
+    /// Get the inner printer, discarding buffered lines.
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P> Printer for RingBufferPrinter<P>
+where
+    P: Printer,
+{
+    fn print(&mut self, s: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(s.to_string());
+    }
+    fn flush(&mut self) {
+        for line in self.lines.drain(..) {
+            self.inner.print(&line);
+        }
+        self.inner.flush();
+    }
+    fn event(&mut self, event: &Event) {
+        self.inner.event(event);
+    }
+}
+
+/// Printer that truncates lines longer than `max_len` characters before
+/// passing them to the inner printer.
+pub struct TruncatingPrinter<P> {
+    inner: P,
+    max_len: usize,
+}
+
+impl<P> TruncatingPrinter<P>
+where
+    P: Printer,
+{
+    /// Create a truncating printer with the given maximum line length.
+    pub fn new(inner: P, max_len: usize) -> Self {
+        Self { inner, max_len }
+    }
+    /// Get the inner printer.
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P> Printer for TruncatingPrinter<P>
+where
+    P: Printer,
+{
+    fn print(&mut self, s: &str) {
+        match s.char_indices().nth(self.max_len) {
+            Some((end, _)) => {
+                let len = s.chars().count();
+                self.inner
+                    .print(&format!("{}... ({} chars total)", &s[..end], len));
+            }
+            None => self.inner.print(s),
+        }
+    }
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+    fn event(&mut self, event: &Event) {
+        self.inner.event(event);
+    }
+}
+
+/// Clock of the timestamps of `TimestampPrinter` and `Checker::set_clock`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clock {
+    /// Seconds since the printer was created or checking started.
+    Monotonic,
+    /// Seconds since the Unix epoch.
+    System,
+}
+
+#[cfg(feature = "std")]
+impl Clock {
+    /// Current time in seconds, measured from `start` if monotonic.
+    pub(crate) fn now(self, start: Instant) -> f64 {
+        let time = match self {
+            Clock::Monotonic => start.elapsed(),
+            Clock::System => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        };
+        time.as_secs_f64()
+    }
+}
+
+/// Printer that prefixes every line with a timestamp, e.g. `[   12.345678]`,
+/// and stamps events that carry none yet.
+///
+/// To timestamp NDJSON output, wrap a `JsonPrinter` in this printer rather
+/// than the other way round, which would prefix the JSON lines.
+#[cfg(feature = "std")]
+pub struct TimestampPrinter<P> {
+    inner: P,
+    clock: Clock,
+    start: Instant,
+}
+
+#[cfg(feature = "std")]
+impl<P> TimestampPrinter<P>
+where
+    P: Printer,
+{
+    /// Create a timestamp printer using the given clock.
+    pub fn new(inner: P, clock: Clock) -> Self {
+        Self {
+            inner,
+            clock,
+            start: Instant::now(),
+        }
+    }
+    /// Get the inner printer.
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P> Printer for TimestampPrinter<P>
+where
+    P: Printer,
+{
+    fn print(&mut self, s: &str) {
+        let time = self.clock.now(self.start);
+        self.inner.print(&format!("[{:>12.6}] {}", time, s));
+    }
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+    fn event(&mut self, event: &Event) {
+        let mut event = *event;
+        if event.time().is_none() {
+            event.set_time(self.clock.now(self.start));
+        }
+        self.inner.event(&event);
+    }
+}
+
+/// Printer that writes every event as one line of JSON (NDJSON) to the inner
+/// printer and drops the human readable text, e.g. for CI.
+///
+/// The inner printer must pass lines on unchanged, see `TimestampPrinter`.
+pub struct JsonPrinter<P> {
+    inner: P,
+}
+
+impl<P> JsonPrinter<P>
+where
+    P: Printer,
+{
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+    /// Get the inner printer.
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P> Printer for JsonPrinter<P>
+where
+    P: Printer,
+{
+    fn print(&mut self, _s: &str) {}
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+    fn event(&mut self, event: &Event) {
+        self.inner.print(&event.to_json());
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn timestamps_prefix_lines() {
+        let mut printer = TimestampPrinter::new(Vec::new(), Clock::Monotonic);
+        printer.print("[ Round 1 ]");
+        let lines = printer.into_inner();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with('['));
+        assert!(lines[0].ends_with("] [ Round 1 ]"));
+        assert_eq!(lines[0].find(']'), Some(13));
+    }
+
+    #[test]
+    fn timestamps_stamp_json_events() {
+        let mut printer = TimestampPrinter::new(JsonPrinter::new(Vec::new()), Clock::Monotonic);
+        printer.print("[ Round 1 ]");
+        printer.event(&Event::Round {
+            round: 1,
+            command: "Open",
+            time: None,
+        });
+        let lines = printer.into_inner().into_inner();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with(r#"{"type":"round","round":1,"command":"Open","time":"#));
+        assert!(lines[0].ends_with('}'));
     }
 }
@@ -0,0 +1,179 @@
+use crate::{port::TestPort, AbstractState, ColorMode, Command, Error, Printer};
+use alloc::{boxed::Box, format, vec::Vec};
+use core::fmt::Debug;
+
+/// One recorded round of a reference target.
+pub struct ReferenceRound<S> {
+    /// Command sent in this round.
+    pub command: Box<dyn Command<S>>,
+    /// Return value of the reference target.
+    pub retv: isize,
+    /// State of the reference target after the command.
+    pub state: S,
+}
+
+/// Differential checker comparing a live target against a recorded run of a
+/// reference target (e.g. a known-good kernel build), instead of a model.
+pub struct ReferenceChecker<T, P, S>
+where
+    T: TestPort<S>,
+    P: Printer,
+    S: AbstractState + Debug,
+{
+    /// Port to comunicate with the live target.
+    port: T,
+    /// Info printer.
+    printer: P,
+    /// Initial state of the reference target.
+    initial: S,
+    /// Recorded rounds of the reference target.
+    reference: Vec<ReferenceRound<S>>,
+    /// Round counter.
+    round: usize,
+    /// Whether to color the output.
+    color: ColorMode,
+}
+
+impl<T, P, S> ReferenceChecker<T, P, S>
+where
+    T: TestPort<S>,
+    P: Printer,
+    S: AbstractState + Debug,
+{
+    /// Construct a reference checker.
+    pub fn new(port: T, printer: P, initial: S, reference: Vec<ReferenceRound<S>>) -> Self {
+        Self {
+            port,
+            printer,
+            initial,
+            reference,
+            round: 0,
+            color: ColorMode::Always,
+        }
+    }
+
+    /// Set when to color the printed headlines and regressions, `Always` by
+    /// default.
+    pub fn set_color_mode(&mut self, color: ColorMode) {
+        self.color = color;
+    }
+
+    /// Replay all recorded rounds on the live target, stopping at the first
+    /// return value or state that differs from the reference. Round 0 is the
+    /// initial state.
+    pub fn run(&mut self) -> Result<(), Error> {
+        self.round = 0;
+        let state = self.retrieve_state()?;
+        if !state.matches(&self.initial) {
+            self.report_state(&state, 0);
+            return Err(Error::StateMismatch);
+        }
+        for i in 0..self.reference.len() {
+            self.round = i + 1;
+            let headline = format!("[ Round {} ]", self.round);
+            self.printer.print(&self.color.paint("1;32", &headline));
+            let command = self.reference[i].command.as_ref();
+            self.printer
+                .print(&format!("Command: {}", command.describe()));
+            self.port.send_command(command)?;
+            let retv = self.port.receive_retv();
+            let expected = self.reference[i].retv;
+            let kind = command.retv_kind();
+            self.printer.print(&format!(
+                "Expected: {}, Got: {}",
+                kind.format(expected),
+                kind.format(retv)
+            ));
+            if retv != expected {
+                self.printer
+                    .print(&self.color.paint("1;31", "Return value regression"));
+                self.printer.flush();
+                return Err(Error::ReturnValueMismatch);
+            }
+            let state = self.retrieve_state()?;
+            if !state.matches(&self.reference[i].state) {
+                self.report_state(&state, i + 1);
+                return Err(Error::StateMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the current (or, after a failed run, the regressing) round.
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Retrieve the whole state from the live target.
+    fn retrieve_state(&mut self) -> Result<S, Error> {
+        self.port.start_state_retrieval()?;
+        while !self.port.retrieve_state_data()? {}
+        self.port.finish_state_retrieval()
+    }
+
+    /// Print a state regression against recorded round `round`.
+    fn report_state(&mut self, state: &S, round: usize) {
+        let expected = if round == 0 {
+            &self.initial
+        } else {
+            &self.reference[round - 1].state
+        };
+        self.printer
+            .print(&self.color.paint("1;31", "State regression"));
+        self.printer.print("Expected:");
+        self.printer.print(&format!("{:?}", expected));
+        self.printer.print("Got:");
+        self.printer.print(&format!("{:?}", state));
+        self.printer.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{counter_target, Add};
+
+    /// Recording of `Add(1)` three times on a counter starting at 0.
+    fn recording() -> Vec<ReferenceRound<u32>> {
+        (1..=3)
+            .map(|n| ReferenceRound {
+                command: Box::new(Add(1)) as Box<dyn Command<u32>>,
+                retv: n as isize,
+                state: n,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_target_passes() {
+        let mut checker = ReferenceChecker::new(counter_target(&[]), Vec::new(), 0, recording());
+        assert_eq!(checker.run(), Ok(()));
+        assert_eq!(checker.round(), 3);
+    }
+
+    #[test]
+    fn reports_first_retv_regression() {
+        let mut checker = ReferenceChecker::new(counter_target(&[2]), Vec::new(), 0, recording());
+        checker.set_color_mode(ColorMode::Never);
+        assert_eq!(checker.run(), Err(Error::ReturnValueMismatch));
+        assert_eq!(checker.round(), 2);
+        assert_eq!(
+            checker.printer[checker.printer.len() - 2..],
+            ["Expected: 0x2, Got: 0x3", "Return value regression"]
+        );
+    }
+
+    #[test]
+    fn reports_first_state_regression() {
+        let mut reference = recording();
+        reference[2].state = 4;
+        let mut checker = ReferenceChecker::new(counter_target(&[]), Vec::new(), 0, reference);
+        checker.set_color_mode(ColorMode::Never);
+        assert_eq!(checker.run(), Err(Error::StateMismatch));
+        assert_eq!(checker.round(), 3);
+        assert_eq!(
+            checker.printer[checker.printer.len() - 5..],
+            ["State regression", "Expected:", "4", "Got:", "3"]
+        );
+    }
+}
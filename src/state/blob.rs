@@ -0,0 +1,73 @@
+use super::AbstractState;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::ops::{Deref, DerefMut};
+
+/// Opaque binary data checked byte-by-byte, e.g. a crypto context.
+///
+/// `Debug` only shows the length and digest, keeping state dumps compact.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct OpaqueBlob(pub Vec<u8>);
+
+impl OpaqueBlob {
+    /// 64-bit FNV-1a digest of the data, stable across runs and platforms.
+    pub fn digest(&self) -> u64 {
+        self.0.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+impl Debug for OpaqueBlob {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OpaqueBlob")
+            .field("len", &self.0.len())
+            .field("digest", &format_args!("{:#018x}", self.digest()))
+            .finish()
+    }
+}
+
+impl AbstractState for OpaqueBlob {
+    fn matches(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+    fn update(&mut self, other: &Self) {
+        self.0.clone_from(&other.0);
+    }
+}
+
+impl Deref for OpaqueBlob {
+    type Target = Vec<u8>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl DerefMut for OpaqueBlob {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, vec};
+
+    #[test]
+    fn blobs_match_byte_by_byte() {
+        let a = OpaqueBlob(vec![1, 2, 3, 4]);
+        assert!(a.matches(&OpaqueBlob(vec![1, 2, 3, 4])));
+        assert!(!a.matches(&OpaqueBlob(vec![1, 2, 0, 4])));
+        assert!(!a.matches(&OpaqueBlob(vec![1, 2, 3])));
+    }
+
+    #[test]
+    fn digest_is_stable() {
+        assert_eq!(OpaqueBlob(vec![]).digest(), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(OpaqueBlob(b"a".to_vec()).digest(), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(
+            format!("{:?}", OpaqueBlob(b"a".to_vec())),
+            "OpaqueBlob { len: 1, digest: 0xaf63dc4c8601ec8c }"
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use super::AbstractState;
+use crate::CheckLevel;
+use alloc::{vec, vec::Vec};
 
 /// A common interval type.
 #[derive(Debug, Clone, Copy, Default)]
@@ -23,6 +25,15 @@ where
         self.right = other.right;
         self.value.update(&other.value);
     }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.left != other.left || self.right != other.right {
+            return Some(CheckLevel::Strict);
+        }
+        self.value.mismatch_level(&other.value)
+    }
+    fn observe(&mut self, other: &Self) {
+        self.value.observe(&other.value);
+    }
 }
 
 impl<T> Interval<T> {
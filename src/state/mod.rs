@@ -1,10 +1,38 @@
+mod blob;
 mod ignored;
 mod interval;
+mod optional;
+mod range;
+mod ring;
+mod sequence;
+mod settling;
+mod tree;
 mod value;
 
+pub use blob::OpaqueBlob;
 pub use ignored::Ignored;
 pub use interval::Interval;
-pub use value::{Value, ValueList, ValueMap, ValueSet};
+pub use optional::Optional;
+pub use range::Range;
+pub use ring::RingBuffer;
+pub use sequence::Sequence;
+pub use settling::Settling;
+pub use tree::Tree;
+pub use value::{
+    FilteredList, Value, ValueList, ValueMap, ValueMultiset, ValueSet, ValueSubset, ValueWindow,
+};
+
+use crate::{CheckLevel, Error};
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt::Debug;
 
 /// Generic Kernel State Type.
 pub trait AbstractState {
@@ -12,6 +40,65 @@ pub trait AbstractState {
     fn matches(&self, other: &Self) -> bool;
     /// Update the current state with the other state.
     fn update(&mut self, other: &Self);
+    /// Update the current state with the other state, reporting failures.
+    ///
+    /// Defaults to the infallible `update`.
+    fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+        self.update(other);
+        Ok(())
+    }
+    /// List the differences to the other state, for mismatch reports.
+    ///
+    /// Like `matches`, `self` is the observed state and `other` the expected
+    /// one. Each entry is the path of a differing element followed by the
+    /// expected and observed values, e.g. `.tasks[2].prio: expected 1, got 2`;
+    /// a differing leaf reports an empty path. Defaults to no details.
+    fn diff(&self, _other: &Self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Check if the current state matches the other state, reporting the
+    /// check level of the most severe mismatch, or `None` if they match.
+    ///
+    /// The checker calls this instead of `matches` and caps the result at
+    /// its own state check level, so `Relaxed` fields only warn. Defaults to
+    /// `matches`, treating any mismatch as `Strict`.
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.matches(other) {
+            None
+        } else {
+            Some(CheckLevel::Strict)
+        }
+    }
+    /// Record the target state observed at the end of a round.
+    ///
+    /// Like `update`, `self` is the model state and `other` the target one.
+    /// The checker calls this exactly once per round, after the checks, so
+    /// types tracking the target over several rounds (e.g. `Settling`) do it
+    /// here instead of in `matches`, which must not have side effects.
+    /// Containers pairing their elements by position or key forward it.
+    /// Defaults to nothing.
+    fn observe(&mut self, _other: &Self) {}
+}
+
+/// Report a differing leaf with an empty path.
+pub(crate) fn leaf_diff<T: Debug + ?Sized>(observed: &T, expected: &T) -> String {
+    format!(": expected {:?}, got {:?}", expected, observed)
+}
+
+/// Prefix the paths of `diffs` with `prefix`.
+pub(crate) fn prefix_diffs(prefix: &str, diffs: Vec<String>) -> impl Iterator<Item = String> + '_ {
+    diffs.into_iter().map(move |d| format!("{}{}", prefix, d))
+}
+
+/// Checking semantics of a field, as reported by the `AbstractState` derive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Checked by value (`Value`, `ValueList`, `ValueSet`, `ValueMap`, primitives).
+    Value,
+    /// Not checked (`Ignored`).
+    Ignored,
+    /// Any other state type, e.g. a nested struct.
+    Nested,
 }
 
 /// Implements AbstractState for some basic types
@@ -22,6 +109,9 @@ macro_rules! impl_AbstractState {
                 self == other
             }
             fn update(&mut self, other: &Self) { *self = other.clone(); }
+            fn diff(&self, other: &Self) -> Vec<String> {
+                if self == other { Vec::new() } else { vec![leaf_diff(self, other)] }
+            }
         })*
     }
 }
@@ -52,4 +142,420 @@ where
             None => *self = None,
         }
     }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        match (self, other) {
+            (Some(a), Some(b)) => a.diff(b),
+            (None, None) => Vec::new(),
+            (Some(_), None) => vec![": expected None, got Some(..)".to_string()],
+            (None, Some(_)) => vec![": expected Some(..), got None".to_string()],
+        }
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        match (self, other) {
+            (Some(a), Some(b)) => a.mismatch_level(b),
+            (None, None) => None,
+            _ => Some(CheckLevel::Strict),
+        }
+    }
+    fn observe(&mut self, other: &Self) {
+        if let (Some(a), Some(b)) = (self, other) {
+            a.observe(b);
+        }
+    }
+}
+
+impl<T> AbstractState for Box<T>
+where
+    T: AbstractState + ?Sized,
+{
+    fn matches(&self, other: &Self) -> bool {
+        (**self).matches(other)
+    }
+    fn update(&mut self, other: &Self) {
+        (**self).update(other);
+    }
+    fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+        (**self).try_update(other)
+    }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        (**self).diff(other)
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        (**self).mismatch_level(other)
+    }
+    fn observe(&mut self, other: &Self) {
+        (**self).observe(other);
+    }
+}
+
+/// Updating clones the inner state if it is shared.
+impl<T> AbstractState for Rc<T>
+where
+    T: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        (**self).matches(other)
+    }
+    fn update(&mut self, other: &Self) {
+        Rc::make_mut(self).update(other);
+    }
+    fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+        Rc::make_mut(self).try_update(other)
+    }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        (**self).diff(other)
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        (**self).mismatch_level(other)
+    }
+    fn observe(&mut self, other: &Self) {
+        Rc::make_mut(self).observe(other);
+    }
+}
+
+/// Keys are checked by equality, values as states. Updating recurses into
+/// the values of common keys.
+impl<K, V> AbstractState for BTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(k, v)| other.get(k).is_some_and(|ov| v.matches(ov)))
+    }
+    fn update(&mut self, other: &Self) {
+        self.retain(|k, _| other.contains_key(k));
+        for (k, ov) in other {
+            match self.get_mut(k) {
+                Some(v) => v.update(ov),
+                None => {
+                    self.insert(k.clone(), ov.clone());
+                }
+            }
+        }
+    }
+    fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+        self.retain(|k, _| other.contains_key(k));
+        for (k, ov) in other {
+            match self.get_mut(k) {
+                Some(v) => v.try_update(ov)?,
+                None => {
+                    self.insert(k.clone(), ov.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        let mut diffs = Vec::new();
+        for (k, v) in self {
+            match other.get(k) {
+                Some(ov) => diffs.extend(prefix_diffs(&format!("[{:?}]", k), v.diff(ov))),
+                None => diffs.push(format!("[{:?}]: unexpected key", k)),
+            }
+        }
+        for k in other.keys().filter(|k| !self.contains_key(k)) {
+            diffs.push(format!("[{:?}]: missing key", k));
+        }
+        diffs
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.len() != other.len() || self.keys().any(|k| !other.contains_key(k)) {
+            return Some(CheckLevel::Strict);
+        }
+        self.iter()
+            .map(|(k, v)| v.mismatch_level(&other[k]))
+            .max()
+            .flatten()
+    }
+    fn observe(&mut self, other: &Self) {
+        for (k, v) in self.iter_mut() {
+            if let Some(ov) = other.get(k) {
+                v.observe(ov);
+            }
+        }
+    }
+}
+
+/// Implements AbstractState for tuples, checking elements pairwise.
+macro_rules! impl_AbstractState_tuple {
+    ($(($($t:ident $i:tt),+)),+) => {
+        $(impl<$($t),+> AbstractState for ($($t,)+)
+        where
+            $($t: AbstractState),+
+        {
+            fn matches(&self, other: &Self) -> bool {
+                $(self.$i.matches(&other.$i))&&+
+            }
+            fn update(&mut self, other: &Self) {
+                $(self.$i.update(&other.$i);)+
+            }
+            fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+                $(self.$i.try_update(&other.$i)?;)+
+                Ok(())
+            }
+            fn diff(&self, other: &Self) -> Vec<String> {
+                let mut diffs = Vec::new();
+                $(diffs.extend(prefix_diffs(concat!(".", stringify!($i)), self.$i.diff(&other.$i)));)+
+                diffs
+            }
+            fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+                let mut level = None;
+                $(level = level.max(self.$i.mismatch_level(&other.$i));)+
+                level
+            }
+            fn observe(&mut self, other: &Self) {
+                $(self.$i.observe(&other.$i);)+
+            }
+        })+
+    }
+}
+
+impl_AbstractState_tuple!((A 0), (A 0, B 1), (A 0, B 1, C 2), (A 0, B 1, C 2, D 3));
+
+impl<T, const N: usize> AbstractState for [T; N]
+where
+    T: AbstractState,
+{
+    fn matches(&self, other: &Self) -> bool {
+        self.iter().zip(other.iter()).all(|(a, b)| a.matches(b))
+    }
+    fn update(&mut self, other: &Self) {
+        for (a, b) in self.iter_mut().zip(other.iter()) {
+            a.update(b);
+        }
+    }
+    fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+        for (a, b) in self.iter_mut().zip(other.iter()) {
+            a.try_update(b)?;
+        }
+        Ok(())
+    }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        let mut diffs = Vec::new();
+        for (i, (a, b)) in self.iter().zip(other.iter()).enumerate() {
+            diffs.extend(prefix_diffs(&format!("[{}]", i), a.diff(b)));
+        }
+        diffs
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        self.iter()
+            .zip(other.iter())
+            .map(|(a, b)| a.mismatch_level(b))
+            .max()
+            .flatten()
+    }
+    fn observe(&mut self, other: &Self) {
+        for (a, b) in self.iter_mut().zip(other.iter()) {
+            a.observe(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Soft;
+
+    #[test]
+    fn map_matches_equal_maps() {
+        let a = BTreeMap::from([(1, 10u32), (2, 20)]);
+        assert!(a.matches(&a.clone()));
+        assert!(a.diff(&a.clone()).is_empty());
+    }
+
+    #[test]
+    fn map_rejects_differing_keys() {
+        let a = BTreeMap::from([(1, 10u32), (2, 20)]);
+        let b = BTreeMap::from([(1, 10u32), (3, 20)]);
+        assert!(!a.matches(&b));
+        assert_eq!(a.mismatch_level(&b), Some(CheckLevel::Strict));
+        assert_eq!(a.diff(&b), vec!["[2]: unexpected key", "[3]: missing key"]);
+    }
+
+    #[test]
+    fn map_rejects_differing_values() {
+        let a = BTreeMap::from([(1, 10u32), (2, 20)]);
+        let b = BTreeMap::from([(1, 10u32), (2, 21)]);
+        assert!(!a.matches(&b));
+        assert_eq!(a.diff(&b), vec!["[2]: expected 21, got 20"]);
+        let c = BTreeMap::from([(1, Soft(1))]);
+        assert_eq!(
+            c.mismatch_level(&BTreeMap::from([(1, Soft(2))])),
+            Some(CheckLevel::Relaxed)
+        );
+    }
+
+    #[test]
+    fn map_update_recurses_into_values() {
+        let mut a = BTreeMap::from([(1, 10u32), (2, 20)]);
+        let b = BTreeMap::from([(2, 21u32), (3, 30)]);
+        a.update(&b);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn containers_keep_relaxed_level() {
+        fn level<S: AbstractState>(a: S, b: S) -> Option<CheckLevel> {
+            a.mismatch_level(&b)
+        }
+        const RELAXED: Option<CheckLevel> = Some(CheckLevel::Relaxed);
+        const STRICT: Option<CheckLevel> = Some(CheckLevel::Strict);
+        let keep = |_: &Soft| true;
+        // A differing relaxed element is relaxed, a differing shape strict.
+        let cases = [
+            ("option", level(Some(Soft(1)), Some(Soft(2))), RELAXED),
+            ("option shape", level(Some(Soft(1)), None), STRICT),
+            ("option none", level(None::<Soft>, None), None),
+            (
+                "box and tuple",
+                level((Box::new(Soft(1)), 2u8), (Box::new(Soft(2)), 2)),
+                RELAXED,
+            ),
+            (
+                "box and tuple strict field",
+                level((Box::new(Soft(1)), 2u8), (Box::new(Soft(2)), 3)),
+                STRICT,
+            ),
+            (
+                "optional",
+                level(Optional(Some(Soft(1))), Optional(Some(Soft(2)))),
+                RELAXED,
+            ),
+            (
+                "optional none",
+                level(Optional(Some(Soft(1))), Optional(None)),
+                None,
+            ),
+            (
+                "interval",
+                level(Interval::new(0, 4, Soft(1)), Interval::new(0, 4, Soft(2))),
+                RELAXED,
+            ),
+            (
+                "interval bounds",
+                level(Interval::new(0, 4, Soft(1)), Interval::new(0, 5, Soft(1))),
+                STRICT,
+            ),
+            (
+                "ring",
+                level(
+                    RingBuffer::new(vec![Soft(1), Soft(2)], 1, 2),
+                    RingBuffer::new(vec![Soft(2), Soft(3)], 0, 2),
+                ),
+                RELAXED,
+            ),
+            (
+                "ring length",
+                level(
+                    RingBuffer::new(vec![Soft(1), Soft(2)], 1, 2),
+                    RingBuffer::new(vec![Soft(2)], 0, 1),
+                ),
+                STRICT,
+            ),
+            (
+                "tree",
+                level(
+                    Tree::new(Soft(0), vec![Tree::leaf(Soft(1))]),
+                    Tree::new(Soft(0), vec![Tree::leaf(Soft(2))]),
+                ),
+                RELAXED,
+            ),
+            (
+                "tree shape",
+                level(
+                    Tree::new(Soft(0), vec![Tree::leaf(Soft(1))]),
+                    Tree::leaf(Soft(0)),
+                ),
+                STRICT,
+            ),
+            (
+                "list",
+                level(
+                    ValueList(vec![Soft(1), Soft(2)]),
+                    ValueList(vec![Soft(1), Soft(3)]),
+                ),
+                RELAXED,
+            ),
+            (
+                "list length",
+                level(ValueList(vec![Soft(1), Soft(2)]), ValueList(vec![Soft(1)])),
+                STRICT,
+            ),
+            (
+                "list equal",
+                level(
+                    ValueList(vec![Soft(1), Soft(2)]),
+                    ValueList(vec![Soft(1), Soft(2)]),
+                ),
+                None,
+            ),
+            (
+                "window",
+                level(
+                    ValueWindow::new(vec![Soft(0), Soft(1)], 1),
+                    ValueWindow::new(vec![Soft(2)], 1),
+                ),
+                RELAXED,
+            ),
+            (
+                "filtered list",
+                level(
+                    FilteredList::new(vec![Soft(1)], keep),
+                    FilteredList::new(vec![Soft(2)], keep),
+                ),
+                RELAXED,
+            ),
+            (
+                "filtered list length",
+                level(
+                    FilteredList::new(vec![Soft(1)], keep),
+                    FilteredList::new(vec![], keep),
+                ),
+                STRICT,
+            ),
+        ];
+        for (name, got, expected) in cases {
+            assert_eq!(got, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn tuples_match_pairwise() {
+        let a = (Value(1u32), Ignored(5u64), 'x', true);
+        assert!(a.matches(&(Value(1), Ignored(6), 'x', true)));
+        let b = (Value(2u32), Ignored(5u64), 'x', false);
+        assert!(!a.matches(&b));
+        assert_eq!(
+            a.diff(&b),
+            [".0: expected 2, got 1", ".3: expected false, got true"]
+        );
+        let mut c = (Value(0u32),);
+        c.update(&(Value(3),));
+        assert_eq!(*c.0, 3);
+    }
+
+    #[test]
+    fn arrays_match_elementwise() {
+        let a = [Value(1u8), Value(2), Value(3)];
+        assert!(a.matches(&[Value(1), Value(2), Value(3)]));
+        let b = [Value(1u8), Value(0), Value(3)];
+        assert!(!a.matches(&b));
+        assert_eq!(a.diff(&b), ["[1]: expected 0, got 2"]);
+        assert_eq!(a.mismatch_level(&b), Some(CheckLevel::Strict));
+    }
+
+    #[test]
+    fn shared_rc_is_cloned_on_update() {
+        let shared = Rc::new(BTreeMap::from([(1, 10u32)]));
+        let mut model = shared.clone();
+        let target = Rc::new(BTreeMap::from([(1, 11u32)]));
+        assert!(!model.matches(&target));
+        model.update(&target);
+        assert!(model.matches(&target));
+        assert_eq!(shared[&1], 10);
+    }
 }
@@ -0,0 +1,88 @@
+use super::AbstractState;
+use crate::CheckLevel;
+use alloc::{string::String, vec::Vec};
+use core::ops::{Deref, DerefMut};
+
+/// Optional value where `None` on either side matches anything, e.g. for a
+/// field that only exists after initialization.
+///
+/// Unlike `Option<T>`, a `Some` never mismatches a `None`. Unlike `Ignored`,
+/// the value is checked once both sides have one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Optional<T>(pub Option<T>);
+
+impl<T> AbstractState for Optional<T>
+where
+    T: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => a.matches(b),
+            _ => true,
+        }
+    }
+    fn update(&mut self, other: &Self) {
+        self.0.update(&other.0);
+    }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => a.diff(b),
+            _ => Vec::new(),
+        }
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => a.mismatch_level(b),
+            _ => None,
+        }
+    }
+    fn observe(&mut self, other: &Self) {
+        self.0.observe(&other.0);
+    }
+}
+
+impl<T> Deref for Optional<T> {
+    type Target = Option<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for Optional<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn none_on_either_side_matches() {
+        let some = Optional(Some(1u32));
+        let none = Optional::<u32>(None);
+        assert!(some.matches(&none));
+        assert!(none.matches(&some));
+        assert!(none.matches(&none));
+        assert!(some.diff(&none).is_empty());
+    }
+
+    #[test]
+    fn some_on_both_sides_is_checked() {
+        let a = Optional(Some(1u32));
+        assert!(a.matches(&Optional(Some(1))));
+        assert!(!a.matches(&Optional(Some(2))));
+        assert_eq!(
+            a.mismatch_level(&Optional(Some(2))),
+            Some(CheckLevel::Strict)
+        );
+        assert_eq!(a.diff(&Optional(Some(2))), [": expected 2, got 1"]);
+    }
+
+    #[test]
+    fn update_fills_in_the_value() {
+        let mut a = Optional::<u32>(None);
+        a.update(&Optional(Some(3)));
+        assert_eq!(*a, Some(3));
+    }
+}
@@ -0,0 +1,81 @@
+use super::AbstractState;
+use core::ops::Sub;
+
+/// Numeric value matched within a tolerance, e.g. a free memory counter.
+///
+/// Two ranges match if their values differ by at most the larger of both
+/// tolerances (inclusive), so the target side can leave its tolerance zero.
+/// The distance of both values must fit in `T`, which always holds for
+/// unsigned types.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Range<T> {
+    /// Expected (model) or observed (target) value.
+    pub value: T,
+    /// Allowed deviation from `value`.
+    pub tolerance: T,
+}
+
+impl<T> Range<T> {
+    pub fn new(value: T, tolerance: T) -> Self {
+        Self { value, tolerance }
+    }
+}
+
+impl<T> AbstractState for Range<T>
+where
+    T: PartialOrd + Sub<Output = T> + Copy,
+{
+    fn matches(&self, other: &Self) -> bool {
+        let tolerance = if self.tolerance > other.tolerance {
+            self.tolerance
+        } else {
+            other.tolerance
+        };
+        // Subtract the smaller value, so neither side can overflow.
+        let distance = if self.value > other.value {
+            self.value - other.value
+        } else {
+            other.value - self.value
+        };
+        distance <= tolerance
+    }
+    fn update(&mut self, other: &Self) {
+        self.value = other.value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_are_inclusive() {
+        let model = Range::new(10u32, 2);
+        assert!(Range::new(8, 0).matches(&model));
+        assert!(Range::new(12, 0).matches(&model));
+        assert!(!Range::new(7, 0).matches(&model));
+        assert!(!Range::new(13, 0).matches(&model));
+    }
+
+    #[test]
+    fn negative_values() {
+        let model = Range::new(-3i32, 2);
+        assert!(Range::new(-5, 0).matches(&model));
+        assert!(Range::new(-1, 0).matches(&model));
+        assert!(!Range::new(-6, 0).matches(&model));
+        assert!(!Range::new(0, 0).matches(&model));
+    }
+
+    #[test]
+    fn larger_tolerance_wins() {
+        assert!(Range::new(5u8, 3).matches(&Range::new(8, 0)));
+        assert!(Range::new(8u8, 0).matches(&Range::new(5, 3)));
+    }
+
+    #[test]
+    fn no_overflow_near_max() {
+        assert!(Range::new(250u8, 0).matches(&Range::new(251, 10)));
+        assert!(Range::new(u8::MAX, 255).matches(&Range::new(0, 0)));
+        assert!(!Range::new(i8::MAX, 0).matches(&Range::new(i8::MAX - 2, 1)));
+    }
+}
@@ -0,0 +1,84 @@
+use super::AbstractState;
+use crate::CheckLevel;
+use alloc::vec::Vec;
+
+/// Circular buffer compared by its logical contents.
+///
+/// `storage` is the raw buffer, and the logical contents are the `len`
+/// elements starting at `head`, wrapping around. Two ring buffers match if
+/// their logical contents match element-by-element, whatever their physical
+/// rotation.
+#[derive(Debug, Clone, Default)]
+pub struct RingBuffer<T> {
+    /// Raw storage.
+    pub storage: Vec<T>,
+    /// Index of the first logical element.
+    pub head: usize,
+    /// Number of logical elements.
+    pub len: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(storage: Vec<T>, head: usize, len: usize) -> Self {
+        Self { storage, head, len }
+    }
+    /// Number of logical elements, at most the storage size.
+    pub fn logical_len(&self) -> usize {
+        self.len.min(self.storage.len())
+    }
+    /// Iterate over the logical contents, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let cap = self.storage.len();
+        (0..self.logical_len()).map(move |i| &self.storage[(self.head + i) % cap])
+    }
+}
+
+impl<T> AbstractState for RingBuffer<T>
+where
+    T: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        if self.logical_len() != other.logical_len() {
+            return false;
+        }
+        self.iter().zip(other.iter()).all(|(a, b)| a.matches(b))
+    }
+    fn update(&mut self, other: &Self) {
+        self.storage = other.storage.clone();
+        self.head = other.head;
+        self.len = other.len;
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.logical_len() != other.logical_len() {
+            return Some(CheckLevel::Strict);
+        }
+        self.iter()
+            .zip(other.iter())
+            .map(|(a, b)| a.mismatch_level(b))
+            .max()
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn rotations_of_same_contents_match() {
+        let a = RingBuffer::new(vec![1, 2, 3, 4], 0, 3);
+        let b = RingBuffer::new(vec![3, 0, 1, 2], 2, 3);
+        assert!(a.matches(&b));
+        assert!(b.matches(&a));
+        assert_eq!(b.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn differing_contents_do_not_match() {
+        let a = RingBuffer::new(vec![1, 2, 3, 4], 0, 3);
+        assert!(!a.matches(&RingBuffer::new(vec![3, 0, 1, 5], 2, 3)));
+        assert!(!a.matches(&RingBuffer::new(vec![3, 1, 2], 0, 3)));
+        assert!(!a.matches(&RingBuffer::new(vec![1, 2, 3, 4], 0, 4)));
+    }
+}
@@ -0,0 +1,96 @@
+use super::AbstractState;
+use core::ops::Add;
+
+/// Sequence number that must advance by exactly `step` between two checks.
+///
+/// The model side remembers the last observed value. When checked as in
+/// `Checker` (`target.matches(&model)`), the target value must equal the last
+/// observed value plus `step`; skipped or repeated values are a mismatch.
+/// After each check the model resynchronizes to the target value in
+/// `observe`, so a single desync is reported once.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sequence<T> {
+    /// Last observed value.
+    value: T,
+    /// Expected increment between two checks.
+    step: T,
+}
+
+impl<T> Sequence<T>
+where
+    T: Copy,
+{
+    pub fn new(value: T, step: T) -> Self {
+        Self { value, step }
+    }
+    /// Get the last observed value.
+    pub fn value(&self) -> T {
+        self.value
+    }
+    /// Get the expected increment.
+    pub fn step(&self) -> T {
+        self.step
+    }
+}
+
+impl<T> AbstractState for Sequence<T>
+where
+    T: Copy + PartialEq + Add<Output = T>,
+{
+    fn matches(&self, other: &Self) -> bool {
+        self.value == other.value + other.step
+    }
+    fn update(&mut self, other: &Self) {
+        self.value = other.value;
+    }
+    fn observe(&mut self, other: &Self) {
+        self.value = other.value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::check_observed;
+    use crate::{Command, Error};
+    use alloc::{vec, vec::Vec};
+
+    /// Command the target answers with the next sequence number.
+    #[derive(Debug)]
+    struct Send;
+
+    impl Command<Sequence<u32>> for Send {
+        fn execute(&self, _state: &mut Sequence<u32>) -> isize {
+            0
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+    }
+
+    /// Check `Send` rounds against a target reporting `observed`, the
+    /// initial value first.
+    fn check(observed: Vec<u32>) -> Result<(), (Error, usize)> {
+        let observed = observed.into_iter().map(|v| Sequence::new(v, 0));
+        check_observed(Sequence::new(0, 1), || Send, observed.collect())
+    }
+
+    #[test]
+    fn advancing_by_step_matches() {
+        assert_eq!(check(vec![5, 6, 7, 8]), Ok(()));
+    }
+
+    #[test]
+    fn jump_by_two_is_reported() {
+        assert_eq!(check(vec![0, 1, 3, 4]), Err((Error::StateMismatch, 2)));
+    }
+
+    #[test]
+    fn matches_has_no_side_effects() {
+        let model = Sequence::new(1, 1);
+        let target = Sequence::new(2, 0);
+        assert!(target.matches(&model));
+        assert!(target.matches(&model));
+        assert_eq!(model.value(), 1);
+    }
+}
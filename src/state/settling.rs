@@ -0,0 +1,112 @@
+use super::AbstractState;
+
+/// Value that the target may take a few rounds to settle to.
+///
+/// When checked as in `Checker` (`target.matches(&model)`), a target value
+/// differing from the model is tolerated for up to `rounds` consecutive checks.
+/// The mismatch is only reported if the value has not settled by then. The
+/// model side counts the unsettled checks in `observe`.
+#[derive(Debug, Clone, Default)]
+pub struct Settling<T> {
+    /// Expected (model) or observed (target) value.
+    pub value: T,
+    /// Number of consecutive checks a differing value is tolerated.
+    rounds: usize,
+    /// Number of consecutive checks the value has differed.
+    unsettled: usize,
+}
+
+impl<T> Settling<T> {
+    pub fn new(value: T, rounds: usize) -> Self {
+        Self {
+            value,
+            rounds,
+            unsettled: 0,
+        }
+    }
+    /// Number of consecutive checks the observed value has differed.
+    pub fn unsettled(&self) -> usize {
+        self.unsettled
+    }
+}
+
+impl<T> AbstractState for Settling<T>
+where
+    T: PartialEq + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        self.value == other.value || other.unsettled < other.rounds
+    }
+    fn update(&mut self, other: &Self) {
+        self.value = other.value.clone();
+        self.unsettled = 0;
+    }
+    fn observe(&mut self, other: &Self) {
+        if self.value == other.value {
+            self.unsettled = 0;
+        } else {
+            self.unsettled += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::check_observed;
+    use crate::{Command, Error};
+    use alloc::{vec, vec::Vec};
+
+    /// Command after which the model expects the value 1.
+    #[derive(Debug)]
+    struct Activate;
+
+    impl Command<Settling<u8>> for Activate {
+        fn execute(&self, state: &mut Settling<u8>) -> isize {
+            state.value = 1;
+            0
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+    }
+
+    /// Check `Activate` rounds against a target reporting `observed`, the
+    /// initial value first, with a tolerance of `rounds`.
+    fn check(rounds: usize, observed: Vec<u8>) -> Result<(), (Error, usize)> {
+        let observed = observed.into_iter().map(|v| Settling::new(v, 0));
+        check_observed(Settling::new(0, rounds), || Activate, observed.collect())
+    }
+
+    #[test]
+    fn settles_within_tolerance() {
+        assert_eq!(check(3, vec![0, 0, 0, 1, 1]), Ok(()));
+    }
+
+    #[test]
+    fn never_settles() {
+        assert_eq!(
+            check(3, vec![0, 0, 0, 0, 0]),
+            Err((Error::StateMismatch, 4))
+        );
+    }
+
+    #[test]
+    fn matches_has_no_side_effects() {
+        let model = Settling::new(1, 1);
+        let target = Settling::new(0, 0);
+        assert!(target.matches(&model));
+        assert!(target.matches(&model));
+        assert_eq!(model.unsettled(), 0);
+    }
+
+    #[test]
+    fn observe_counts_unsettled_checks() {
+        let mut model = Settling::new(1, 1);
+        let target = Settling::new(0, 0);
+        model.observe(&target);
+        assert!(!target.matches(&model));
+        model.observe(&Settling::new(1, 0));
+        assert!(target.matches(&model));
+    }
+}
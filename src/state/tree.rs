@@ -0,0 +1,124 @@
+use super::AbstractState;
+use crate::CheckLevel;
+use alloc::vec::Vec;
+
+/// Tree of states, e.g. a process hierarchy.
+///
+/// Two trees match if they have the same shape (same number of children at
+/// every node, in the same order) and every pair of corresponding node values
+/// matches.
+#[derive(Debug, Clone, Default)]
+pub struct Tree<T> {
+    /// Value of this node.
+    pub value: T,
+    /// Child subtrees.
+    pub children: Vec<Tree<T>>,
+}
+
+impl<T> Tree<T> {
+    pub fn new(value: T, children: Vec<Tree<T>>) -> Self {
+        Self { value, children }
+    }
+    /// Create a tree with a single node.
+    pub fn leaf(value: T) -> Self {
+        Self::new(value, Vec::new())
+    }
+    /// Number of nodes in the tree.
+    pub fn size(&self) -> usize {
+        1 + self.children.iter().map(Tree::size).sum::<usize>()
+    }
+}
+
+impl<T> AbstractState for Tree<T>
+where
+    T: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        self.value.matches(&other.value)
+            && self.children.len() == other.children.len()
+            && self
+                .children
+                .iter()
+                .zip(other.children.iter())
+                .all(|(a, b)| a.matches(b))
+    }
+    fn update(&mut self, other: &Self) {
+        self.value.update(&other.value);
+        self.children = other.children.clone();
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.children.len() != other.children.len() {
+            return Some(CheckLevel::Strict);
+        }
+        self.children
+            .iter()
+            .zip(other.children.iter())
+            .map(|(a, b)| a.mismatch_level(b))
+            .fold(self.value.mismatch_level(&other.value), Option::max)
+    }
+    fn observe(&mut self, other: &Self) {
+        self.value.observe(&other.value);
+        for (a, b) in self.children.iter_mut().zip(other.children.iter()) {
+            a.observe(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Ignored;
+    use alloc::vec;
+
+    /// Process tree node: an unchecked pid and a checked priority.
+    fn task(
+        pid: u32,
+        prio: u8,
+        children: Vec<Tree<(Ignored<u32>, u8)>>,
+    ) -> Tree<(Ignored<u32>, u8)> {
+        Tree::new((Ignored(pid), prio), children)
+    }
+
+    #[test]
+    fn same_shape_matches_whatever_the_pids() {
+        let a = task(
+            1,
+            0,
+            vec![task(2, 1, vec![task(4, 2, vec![])]), task(3, 1, vec![])],
+        );
+        let b = task(
+            10,
+            0,
+            vec![task(30, 1, vec![task(20, 2, vec![])]), task(40, 1, vec![])],
+        );
+        assert!(a.matches(&b));
+        assert_eq!(a.size(), 4);
+    }
+
+    #[test]
+    fn different_shape_or_value_does_not_match() {
+        let a = task(
+            1,
+            0,
+            vec![task(2, 1, vec![task(4, 2, vec![])]), task(3, 1, vec![])],
+        );
+        let moved = task(
+            1,
+            0,
+            vec![task(2, 1, vec![]), task(3, 1, vec![task(4, 2, vec![])])],
+        );
+        assert!(!a.matches(&moved));
+        let flat = task(
+            1,
+            0,
+            vec![task(2, 1, vec![]), task(3, 1, vec![]), task(4, 2, vec![])],
+        );
+        assert!(!a.matches(&flat));
+        let reprio = task(
+            1,
+            0,
+            vec![task(2, 1, vec![task(4, 3, vec![])]), task(3, 1, vec![])],
+        );
+        assert!(!a.matches(&reprio));
+    }
+}
@@ -1,6 +1,9 @@
-use super::AbstractState;
+use super::{leaf_diff, prefix_diffs, AbstractState};
+use crate::{CheckLevel, Error};
+use alloc::collections::BTreeMap;
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Debug;
 use core::ops::{Deref, DerefMut};
-use std::collections::BTreeMap;
 
 /// Type that is checked value-by-value.
 #[derive(Debug, Clone, Copy, Default)]
@@ -8,7 +11,7 @@ pub struct Value<T>(pub T);
 
 impl<T> AbstractState for Value<T>
 where
-    T: Eq + Clone,
+    T: Eq + Clone + Debug,
 {
     fn matches(&self, other: &Self) -> bool {
         self.0 == other.0
@@ -16,6 +19,13 @@ where
     fn update(&mut self, other: &Self) {
         self.0 = other.0.clone();
     }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        if self.0 == other.0 {
+            Vec::new()
+        } else {
+            vec![leaf_diff(&self.0, &other.0)]
+        }
+    }
 }
 
 impl<T> Deref for Value<T> {
@@ -34,7 +44,7 @@ impl<T> DerefMut for Value<T> {
 #[derive(Debug, Clone, Default)]
 pub struct ValueList<T>(pub Vec<T>);
 
-impl<'a, T> AbstractState for ValueList<T>
+impl<T> AbstractState for ValueList<T>
 where
     T: AbstractState + Clone,
 {
@@ -42,11 +52,57 @@ where
         if self.0.len() != other.0.len() {
             return false;
         }
+        if self.0.is_empty() {
+            return true;
+        }
         self.0.iter().zip(other.0.iter()).all(|(a, b)| a.matches(b))
     }
     fn update(&mut self, other: &Self) {
         self.0 = other.0.clone();
     }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        let mut diffs = Vec::new();
+        if self.0.len() != other.0.len() {
+            diffs.push(format!(
+                ".len(){}",
+                leaf_diff(&self.0.len(), &other.0.len())
+            ));
+        }
+        for (i, (a, b)) in self.0.iter().zip(other.0.iter()).enumerate() {
+            diffs.extend(prefix_diffs(&format!("[{}]", i), a.diff(b)));
+        }
+        diffs
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.0.len() != other.0.len() {
+            return Some(CheckLevel::Strict);
+        }
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| a.mismatch_level(b))
+            .max()
+            .flatten()
+    }
+    fn observe(&mut self, other: &Self) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            a.observe(b);
+        }
+    }
+}
+
+impl<T> ValueList<T>
+where
+    T: AbstractState,
+{
+    /// Check if the list contains an element matching `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        self.0.iter().any(|a| a.matches(value))
+    }
+    /// Get the index of the first element matching `value`.
+    pub fn index_of(&self, value: &T) -> Option<usize> {
+        self.0.iter().position(|a| a.matches(value))
+    }
 }
 
 impl<T> Deref for ValueList<T> {
@@ -61,11 +117,161 @@ impl<T> DerefMut for ValueList<T> {
     }
 }
 
+/// Ordered list of values where only elements selected by `filter` are
+/// checked, e.g. to leave out kernel-internal entries such as an idle task.
+///
+/// Each side is filtered with its own predicate, then the selected elements
+/// are checked value-by-value as in `ValueList`.
+#[derive(Debug, Clone)]
+pub struct FilteredList<T> {
+    /// All elements, including filtered-out ones.
+    pub items: Vec<T>,
+    /// Selects the elements that participate in matching.
+    pub filter: fn(&T) -> bool,
+}
+
+impl<T> FilteredList<T> {
+    pub fn new(items: Vec<T>, filter: fn(&T) -> bool) -> Self {
+        Self { items, filter }
+    }
+    /// Iterate over the elements that participate in matching.
+    pub fn filtered(&self) -> impl Iterator<Item = &T> {
+        self.items.iter().filter(move |a| (self.filter)(a))
+    }
+}
+
+impl<T> AbstractState for FilteredList<T>
+where
+    T: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        let mut a = self.filtered();
+        let mut b = other.filtered();
+        loop {
+            match (a.next(), b.next()) {
+                (Some(x), Some(y)) if x.matches(y) => continue,
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
+    }
+    fn update(&mut self, other: &Self) {
+        self.items = other.items.clone();
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        let mut a = self.filtered();
+        let mut b = other.filtered();
+        let mut level = None;
+        loop {
+            match (a.next(), b.next()) {
+                (Some(x), Some(y)) => level = level.max(x.mismatch_level(y)),
+                (None, None) => return level,
+                _ => return Some(CheckLevel::Strict),
+            }
+        }
+    }
+}
+
+impl<T> Deref for FilteredList<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+impl<T> DerefMut for FilteredList<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
+/// Ordered list where only the last `window` elements are checked, e.g. a
+/// log of which only the most recent entries are observable.
+///
+/// The suffixes of both sides are checked value-by-value as in `ValueList`,
+/// using the larger window of both sides. Older elements and the overall
+/// length are ignored, except that a list shorter than the window must
+/// match in full.
+#[derive(Debug, Clone, Default)]
+pub struct ValueWindow<T> {
+    /// All elements, oldest first.
+    pub items: Vec<T>,
+    /// Number of trailing elements that are checked.
+    pub window: usize,
+}
+
+impl<T> ValueWindow<T> {
+    pub fn new(items: Vec<T>, window: usize) -> Self {
+        Self { items, window }
+    }
+    /// Get the last `window` elements (or all, if fewer).
+    pub fn suffix(&self, window: usize) -> &[T] {
+        &self.items[self.items.len().saturating_sub(window)..]
+    }
+}
+
+impl<T> AbstractState for ValueWindow<T>
+where
+    T: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        let window = self.window.max(other.window);
+        let (a, b) = (self.suffix(window), other.suffix(window));
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.matches(y))
+    }
+    fn update(&mut self, other: &Self) {
+        self.items = other.items.clone();
+    }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        let window = self.window.max(other.window);
+        let (a, b) = (self.suffix(window), other.suffix(window));
+        if a.len() != b.len() {
+            return vec![format!(
+                ".len(){}",
+                leaf_diff(&self.items.len(), &other.items.len())
+            )];
+        }
+        let offset = self.items.len() - a.len();
+        let mut diffs = Vec::new();
+        for (i, (x, y)) in a.iter().zip(b).enumerate() {
+            diffs.extend(prefix_diffs(&format!("[{}]", offset + i), x.diff(y)));
+        }
+        diffs
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        let window = self.window.max(other.window);
+        let (a, b) = (self.suffix(window), other.suffix(window));
+        if a.len() != b.len() {
+            return Some(CheckLevel::Strict);
+        }
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| x.mismatch_level(y))
+            .max()
+            .flatten()
+    }
+}
+
+impl<T> Deref for ValueWindow<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+impl<T> DerefMut for ValueWindow<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
 /// Unordered set of values that are checked value-by-value.
+///
+/// Every element must be paired with a distinct matching element on the
+/// other side, so duplicates count: `[1, 2, 2]` does not match `[1, 1, 2]`.
+/// A pairing is found whenever one exists, whatever the order of elements.
 #[derive(Debug, Clone, Default)]
 pub struct ValueSet<T>(pub Vec<T>);
 
-impl<'a, T> AbstractState for ValueSet<T>
+impl<T> AbstractState for ValueSet<T>
 where
     T: AbstractState + Clone,
 {
@@ -73,11 +279,86 @@ where
         if self.0.len() != other.0.len() {
             return false;
         }
-        self.0.iter().any(|a| other.0.iter().any(|b| a.matches(b)))
+        if self.0.is_empty() {
+            return true;
+        }
+        pair_all(&self.0, &other.0, |a, b| a.matches(b))
     }
     fn update(&mut self, other: &Self) {
         self.0 = other.0.clone();
     }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.0.len() != other.0.len() {
+            return Some(CheckLevel::Strict);
+        }
+        pairing_level(&self.0, &other.0)
+    }
+}
+
+/// Check if every element of `a` can be paired with a distinct element of
+/// `b` it is compatible with.
+///
+/// Uses augmenting paths (bipartite matching), so the result does not depend
+/// on the order of elements, even for matchers that are not an equivalence,
+/// such as `Optional`.
+fn pair_all<T, F>(a: &[T], b: &[T], compatible: F) -> bool
+where
+    F: Fn(&T, &T) -> bool,
+{
+    let edges: Vec<Vec<usize>> = a
+        .iter()
+        .map(|x| (0..b.len()).filter(|&j| compatible(x, &b[j])).collect())
+        .collect();
+    // partner[j] is the element of `a` paired with b[j].
+    let mut partner = vec![None; b.len()];
+    (0..a.len()).all(|i| augment(&edges, i, &mut partner, &mut vec![false; b.len()]))
+}
+
+/// Level of the least severe pairing of every element of `a` with a distinct
+/// element of `b`: `None` if all pairs can match, `Relaxed` if all pairs can
+/// at most mismatch relaxed, `Strict` otherwise.
+fn pairing_level<T>(a: &[T], b: &[T]) -> Option<CheckLevel>
+where
+    T: AbstractState,
+{
+    if pair_all(a, b, |x, y| x.matches(y)) {
+        None
+    } else if pair_all(a, b, |x, y| x.mismatch_level(y) < Some(CheckLevel::Strict)) {
+        Some(CheckLevel::Relaxed)
+    } else {
+        Some(CheckLevel::Strict)
+    }
+}
+
+/// Find a partner for `a[i]`, moving the partners of earlier elements along
+/// an augmenting path if needed.
+fn augment(
+    edges: &[Vec<usize>],
+    i: usize,
+    partner: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for &j in &edges[i] {
+        if visited[j] {
+            continue;
+        }
+        visited[j] = true;
+        if partner[j].is_none_or(|k| augment(edges, k, partner, visited)) {
+            partner[j] = Some(i);
+            return true;
+        }
+    }
+    false
+}
+
+impl<T> ValueSet<T>
+where
+    T: AbstractState,
+{
+    /// Check if the set contains an element matching `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        self.0.iter().any(|a| a.matches(value))
+    }
 }
 
 impl<T> Deref for ValueSet<T> {
@@ -92,6 +373,113 @@ impl<T> DerefMut for ValueSet<T> {
     }
 }
 
+/// Unordered set of values where one side may hold only part of the other,
+/// e.g. a cache that may have evicted entries.
+///
+/// When checked as in `Checker` (`target.matches(&model)`), every target
+/// element must be paired with a distinct matching model element, whatever
+/// the lengths. Duplicates count as in `ValueSet`.
+#[derive(Debug, Clone, Default)]
+pub struct ValueSubset<T>(pub Vec<T>);
+
+impl<T> AbstractState for ValueSubset<T>
+where
+    T: AbstractState + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        self.0.len() <= other.0.len() && pair_all(&self.0, &other.0, |a, b| a.matches(b))
+    }
+    fn update(&mut self, other: &Self) {
+        self.0 = other.0.clone();
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        if self.0.len() > other.0.len() {
+            return Some(CheckLevel::Strict);
+        }
+        pairing_level(&self.0, &other.0)
+    }
+}
+
+impl<T> Deref for ValueSubset<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for ValueSubset<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Multiset of values with explicit multiplicities. Values are checked by
+/// equality and must occur the same number of times on both sides.
+///
+/// A value with a zero count is treated as absent.
+#[derive(Debug, Clone, Default)]
+pub struct ValueMultiset<T>(pub BTreeMap<T, usize>)
+where
+    T: Ord;
+
+impl<T> ValueMultiset<T>
+where
+    T: Ord,
+{
+    /// Build a multiset from a flat list of values.
+    pub fn from_list(list: impl IntoIterator<Item = T>) -> Self {
+        let mut map = BTreeMap::new();
+        for value in list {
+            *map.entry(value).or_insert(0) += 1;
+        }
+        Self(map)
+    }
+    /// Flatten the multiset into a sorted list of values.
+    pub fn to_list(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.0
+            .iter()
+            .flat_map(|(value, &count)| core::iter::repeat_n(value.clone(), count))
+            .collect()
+    }
+    /// Get the multiplicity of a value.
+    pub fn count(&self, value: &T) -> usize {
+        self.0.get(value).copied().unwrap_or(0)
+    }
+}
+
+impl<T> AbstractState for ValueMultiset<T>
+where
+    T: Ord + Clone,
+{
+    fn matches(&self, other: &Self) -> bool {
+        self.0.iter().all(|(v, &c)| other.count(v) == c)
+            && other.0.iter().all(|(v, &c)| self.count(v) == c)
+    }
+    fn update(&mut self, other: &Self) {
+        self.0 = other.0.clone();
+    }
+}
+
+impl<T> Deref for ValueMultiset<T>
+where
+    T: Ord,
+{
+    type Target = BTreeMap<T, usize>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for ValueMultiset<T>
+where
+    T: Ord,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 /// Map of values. Keys are checked by equality.
 #[derive(Debug, Clone, Default)]
 pub struct ValueMap<K, V>(pub BTreeMap<K, V>)
@@ -100,19 +488,26 @@ where
 
 impl<K, V> AbstractState for ValueMap<K, V>
 where
-    K: Ord + Clone,
+    K: Ord + Clone + Debug,
     V: AbstractState + Clone,
 {
     fn matches(&self, other: &Self) -> bool {
-        if self.0.len() != other.0.len() {
-            return false;
-        }
-        self.0
-            .iter()
-            .all(|(k, v)| other.0.get(k).map_or(false, |ov| v.matches(ov)))
+        self.0.matches(&other.0)
     }
     fn update(&mut self, other: &Self) {
-        self.0 = other.0.clone();
+        self.0.update(&other.0);
+    }
+    fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+        self.0.try_update(&other.0)
+    }
+    fn diff(&self, other: &Self) -> Vec<String> {
+        self.0.diff(&other.0)
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        self.0.mismatch_level(&other.0)
+    }
+    fn observe(&mut self, other: &Self) {
+        self.0.observe(&other.0);
     }
 }
 
@@ -133,3 +528,191 @@ where
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Optional;
+    use crate::testing::Soft;
+
+    const RELAXED: Option<CheckLevel> = Some(CheckLevel::Relaxed);
+    const STRICT: Option<CheckLevel> = Some(CheckLevel::Strict);
+
+    #[test]
+    fn sets_keep_relaxed_level() {
+        let a = ValueSet(vec![Soft(1), Soft(2)]);
+        assert_eq!(a.mismatch_level(&ValueSet(vec![Soft(2), Soft(1)])), None);
+        assert_eq!(a.mismatch_level(&ValueSet(vec![Soft(2), Soft(3)])), RELAXED);
+        assert_eq!(a.mismatch_level(&ValueSet(vec![Soft(2)])), STRICT);
+        let b = ValueSubset(vec![Soft(4)]);
+        assert_eq!(
+            b.mismatch_level(&ValueSubset(vec![Soft(1), Soft(2)])),
+            RELAXED
+        );
+        assert_eq!(b.mismatch_level(&ValueSubset(vec![])), STRICT);
+    }
+
+    #[test]
+    fn map_delegates_to_btree_map() {
+        let a = ValueMap(BTreeMap::from([(1, Soft(1)), (2, Soft(2))]));
+        let b = ValueMap(BTreeMap::from([(1, Soft(1)), (2, Soft(3))]));
+        assert_eq!(a.mismatch_level(&b), RELAXED);
+        assert_eq!(a.0.mismatch_level(&b.0), RELAXED);
+        let mut c = a.clone();
+        assert_eq!(c.try_update(&b), Ok(()));
+        assert!(c.matches(&b));
+    }
+
+    #[test]
+    fn set_matches_permutations() {
+        assert!(ValueSet(vec![1, 2, 3]).matches(&ValueSet(vec![3, 1, 2])));
+        assert!(ValueSet::<u8>(vec![]).matches(&ValueSet(vec![])));
+    }
+
+    #[test]
+    fn set_counts_duplicates() {
+        assert!(ValueSet(vec![1, 1, 2]).matches(&ValueSet(vec![1, 2, 1])));
+        assert!(!ValueSet(vec![1, 1, 2]).matches(&ValueSet(vec![1, 2, 2])));
+        assert!(!ValueSet(vec![1, 2, 3]).matches(&ValueSet(vec![1, 9, 9])));
+        assert!(!ValueSet(vec![1, 2]).matches(&ValueSet(vec![1, 2, 2])));
+    }
+
+    #[test]
+    fn subset_matches_proper_subsets() {
+        assert!(ValueSubset(vec![2]).matches(&ValueSubset(vec![1, 2, 3])));
+        assert!(ValueSubset::<u8>(vec![]).matches(&ValueSubset(vec![1])));
+    }
+
+    #[test]
+    fn subset_matches_equal_sets() {
+        assert!(ValueSubset(vec![3, 1, 2]).matches(&ValueSubset(vec![1, 2, 3])));
+    }
+
+    #[test]
+    fn subset_rejects_non_subsets() {
+        assert!(!ValueSubset(vec![4]).matches(&ValueSubset(vec![1, 2, 3])));
+        assert!(!ValueSubset(vec![1, 1]).matches(&ValueSubset(vec![1, 2, 3])));
+        assert!(!ValueSubset(vec![1, 2, 3]).matches(&ValueSubset(vec![1, 2])));
+    }
+
+    #[test]
+    fn subset_pairs_non_equivalence_matchers() {
+        let a = ValueSubset(vec![Optional(None), Optional(Some(1))]);
+        let b = ValueSubset(vec![
+            Optional(Some(1)),
+            Optional(Some(2)),
+            Optional(Some(3)),
+        ]);
+        assert!(a.matches(&b));
+    }
+
+    #[test]
+    fn set_pairs_non_equivalence_matchers() {
+        let a = ValueSet(vec![Optional(None), Optional(Some(1))]);
+        let b = ValueSet(vec![Optional(Some(1)), Optional(Some(2))]);
+        assert!(a.matches(&b));
+        assert!(b.matches(&a));
+    }
+
+    #[test]
+    fn membership_queries() {
+        let list = ValueList(vec![1, 2, 2, 3]);
+        assert!(list.contains(&2));
+        assert!(!list.contains(&4));
+        assert_eq!(list.index_of(&2), Some(1));
+        assert_eq!(list.index_of(&4), None);
+        let set = ValueSet(vec![3, 1]);
+        assert!(set.contains(&1));
+        assert!(!set.contains(&2));
+
+        let list = ValueList::<u8>(vec![]);
+        assert!(!list.contains(&0));
+        assert_eq!(list.index_of(&0), None);
+        assert!(!ValueSet::<u8>(vec![]).contains(&0));
+    }
+
+    /// Select all tasks but the idle task, pid 0.
+    fn not_idle(task: &(u32, u8)) -> bool {
+        task.0 != 0
+    }
+
+    #[test]
+    fn filtered_list_ignores_filtered_out_elements() {
+        let model = FilteredList::new(vec![(1, 5), (2, 7)], not_idle);
+        let target = FilteredList::new(vec![(0, 0), (1, 5), (2, 7)], not_idle);
+        assert!(target.matches(&model));
+        let idle_differs = FilteredList::new(vec![(1, 5), (0, 9), (2, 7)], not_idle);
+        assert!(idle_differs.matches(&target));
+    }
+
+    #[test]
+    fn filtered_list_reports_selected_divergence() {
+        let model = FilteredList::new(vec![(0, 0), (1, 5), (2, 7)], not_idle);
+        let target = FilteredList::new(vec![(0, 0), (1, 5), (2, 8)], not_idle);
+        assert!(!target.matches(&model));
+        let missing = FilteredList::new(vec![(0, 0), (1, 5)], not_idle);
+        assert!(!missing.matches(&model));
+    }
+
+    #[test]
+    fn multiset_compares_multiplicities() {
+        let a = ValueMultiset(BTreeMap::from([(1, 2), (2, 1)]));
+        assert!(a.matches(&ValueMultiset::from_list([2, 1, 1])));
+        assert!(a.matches(&ValueMultiset(BTreeMap::from([(1, 2), (2, 1), (3, 0)]))));
+        assert!(!a.matches(&ValueMultiset::from_list([1, 2, 2])));
+        assert!(!a.matches(&ValueMultiset::from_list([1, 1, 2, 3])));
+    }
+
+    #[test]
+    fn multiset_round_trips_through_list() {
+        let a = ValueMultiset::from_list([3, 1, 3, 2, 3]);
+        assert_eq!(a.count(&3), 3);
+        assert_eq!(a.to_list(), [1, 2, 3, 3, 3]);
+        assert!(ValueMultiset::from_list(a.to_list()).matches(&a));
+    }
+
+    #[test]
+    fn list_diff_names_each_differing_element() {
+        let a = ValueList(vec![1u32, 2, 3]);
+        assert!(a.diff(&a.clone()).is_empty());
+        assert_eq!(
+            a.diff(&ValueList(vec![1, 5, 4])),
+            ["[1]: expected 5, got 2", "[2]: expected 4, got 3"]
+        );
+        assert_eq!(
+            a.diff(&ValueList(vec![1, 5])),
+            [".len(): expected 2, got 3", "[1]: expected 5, got 2"]
+        );
+    }
+
+    #[test]
+    fn map_diff_prefixes_nested_paths() {
+        let a = ValueMap(BTreeMap::from([(1, ValueList(vec![0u32, 1]))]));
+        let b = ValueMap(BTreeMap::from([(1, ValueList(vec![0u32, 2]))]));
+        assert_eq!(a.diff(&b), ["[1][1]: expected 2, got 1"]);
+    }
+
+    #[test]
+    fn windows_of_different_lengths_match_on_suffix() {
+        let model = ValueWindow::new(vec![1u32, 2, 3, 4], 2);
+        let target = ValueWindow::new(vec![9u32, 3, 4], 2);
+        assert!(model.matches(&target));
+        assert!(model.diff(&target).is_empty());
+        assert!(!model.matches(&ValueWindow::new(vec![3u32, 5], 2)));
+        assert_eq!(
+            model.diff(&ValueWindow::new(vec![3u32, 5], 2)),
+            ["[3]: expected 5, got 4"]
+        );
+        // Shorter than the window on one side.
+        assert!(!model.matches(&ValueWindow::new(vec![4u32], 2)));
+        assert!(ValueWindow::new(vec![4u32], 2).matches(&ValueWindow::new(vec![4u32], 2)));
+    }
+
+    #[test]
+    fn larger_window_wins() {
+        let narrow = ValueWindow::new(vec![1u32, 2, 3], 1);
+        let wide = ValueWindow::new(vec![0u32, 2, 3], 3);
+        assert!(!narrow.matches(&wide));
+        assert!(narrow.matches(&ValueWindow::new(vec![0u32, 2, 3], 2)));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::MismatchKind;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Execution counters of a command kind or category.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommandStats {
+    /// Number of rounds the command was executed in.
+    pub count: usize,
+    /// Number of those rounds with at least one mismatch.
+    pub failed: usize,
+}
+
+impl CommandStats {
+    /// Ratio of passed rounds to executed rounds.
+    pub fn pass_rate(&self) -> f64 {
+        if self.count == 0 {
+            1.0
+        } else {
+            (self.count - self.failed) as f64 / self.count as f64
+        }
+    }
+}
+
+/// Rounds in which mismatches happened, per kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MismatchReport {
+    /// Rounds with a return value mismatch.
+    pub retv_rounds: Vec<usize>,
+    /// Rounds with a state mismatch.
+    pub state_rounds: Vec<usize>,
+}
+
+impl MismatchReport {
+    /// Total number of mismatches.
+    pub fn total(&self) -> usize {
+        self.retv_rounds.len() + self.state_rounds.len()
+    }
+    /// Check if no mismatch happened.
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
+
+/// Statistics collected by the checker.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    /// Number of finished rounds.
+    pub rounds: usize,
+    /// Number of rounds with at least one mismatch.
+    pub failed_rounds: usize,
+    /// Number of return value mismatches.
+    pub retv_mismatches: usize,
+    /// Number of state mismatches.
+    pub state_mismatches: usize,
+    /// Number of consecutive rounds without mismatch.
+    pub streak: usize,
+    /// Counters per command kind, keyed by command name.
+    pub commands: BTreeMap<String, CommandStats>,
+    /// Counters per command category.
+    pub categories: BTreeMap<&'static str, CommandStats>,
+    /// Whether the current round has a mismatch.
+    round_failed: bool,
+    /// Name and category of the current command.
+    current: Option<(String, &'static str)>,
+    /// Round and kind of every mismatch, in order.
+    mismatches: Vec<(usize, MismatchKind)>,
+}
+
+impl Stats {
+    /// Record the command executed in the current round.
+    pub(crate) fn record_command(&mut self, name: String, category: &'static str) {
+        self.commands.entry(name.clone()).or_default().count += 1;
+        self.categories.entry(category).or_default().count += 1;
+        self.current = Some((name, category));
+    }
+
+    /// Record a return value mismatch in the current round.
+    pub(crate) fn record_retv_mismatch(&mut self, round: usize) {
+        self.retv_mismatches += 1;
+        self.round_failed = true;
+        self.mismatches.push((round, MismatchKind::Retv));
+    }
+
+    /// Record a state mismatch in the current round.
+    pub(crate) fn record_state_mismatch(&mut self, round: usize) {
+        self.state_mismatches += 1;
+        self.round_failed = true;
+        self.mismatches.push((round, MismatchKind::State));
+    }
+
+    /// Finish the current round.
+    pub(crate) fn finish_round(&mut self) {
+        self.rounds += 1;
+        if self.round_failed {
+            self.failed_rounds += 1;
+            self.streak = 0;
+            if let Some((name, category)) = &self.current {
+                self.commands.entry(name.clone()).or_default().failed += 1;
+                self.categories.entry(category).or_default().failed += 1;
+            }
+        } else {
+            self.streak += 1;
+        }
+        self.round_failed = false;
+        self.current = None;
+    }
+
+    /// Names of the command kinds with fewer than `min` executions, sorted.
+    ///
+    /// Covers the executed kinds and the `expected` ones, so a kind that
+    /// never ran counts as executed 0 times.
+    pub fn under_covered<'a>(&'a self, expected: &[&'a str], min: usize) -> Vec<&'a str> {
+        let mut names: BTreeSet<&str> = self.commands.keys().map(String::as_str).collect();
+        names.extend(expected.iter().copied());
+        names
+            .into_iter()
+            .filter(|&name| self.commands.get(name).map_or(0, |stats| stats.count) < min)
+            .collect()
+    }
+
+    /// Rounds in which mismatches happened, relaxed or strict.
+    pub fn mismatch_report(&self) -> MismatchReport {
+        let mut report = MismatchReport::default();
+        for &(round, kind) in &self.mismatches {
+            match kind {
+                MismatchKind::Retv => report.retv_rounds.push(round),
+                MismatchKind::State => report.state_rounds.push(round),
+            }
+        }
+        report
+    }
+
+    /// Ratio of failed rounds to finished rounds.
+    pub fn mismatch_rate(&self) -> f64 {
+        if self.rounds == 0 {
+            0.0
+        } else {
+            self.failed_rounds as f64 / self.rounds as f64
+        }
+    }
+}
+
+/// Name of a command kind: the leading identifier of its `Debug` output, e.g.
+/// `Spawn` for `Spawn { pid: 1 }`.
+pub(crate) fn command_name(debug: &str) -> String {
+    let end = debug
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(debug.len());
+    if end == 0 {
+        debug.to_string()
+    } else {
+        debug[..end].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn under_covered_lists_rare_and_missing_commands() {
+        let mut stats = Stats::default();
+        for name in ["Open", "Open", "Read", "Read", "Read", "Read", "Read"] {
+            stats.record_command(name.to_string(), "fs");
+            stats.finish_round();
+        }
+        assert_eq!(stats.under_covered(&[], 5), vec!["Open"]);
+        assert_eq!(
+            stats.under_covered(&["Read", "Close"], 5),
+            vec!["Close", "Open"]
+        );
+        assert!(stats.under_covered(&["Read"], 2).is_empty());
+    }
+
+    #[test]
+    fn command_name_is_leading_identifier() {
+        assert_eq!(command_name("Spawn { pid: 1 }"), "Spawn");
+        assert_eq!(command_name("Close(3)"), "Close");
+    }
+}
@@ -0,0 +1,174 @@
+//! Helpers shared by the unit tests.
+
+use crate::{
+    AbstractState, CheckLevel, Checker, ClosureTestPort, Command, CommandGenerator, Error, Printer,
+    ReplayCommander, TestPort,
+};
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+
+/// Printer discarding all output.
+pub(crate) struct NullPrinter;
+
+impl Printer for NullPrinter {
+    fn print(&mut self, _s: &str) {}
+}
+
+/// Printer collecting all lines.
+impl Printer for Vec<String> {
+    fn print(&mut self, s: &str) {
+        self.push(s.to_string());
+    }
+}
+
+/// Printer collecting all lines into a vector shared with the test.
+#[derive(Clone, Default)]
+pub(crate) struct SharedLines(pub Rc<RefCell<Vec<String>>>);
+
+impl SharedLines {
+    /// Copy of the lines printed so far.
+    pub fn lines(&self) -> Vec<String> {
+        self.0.borrow().clone()
+    }
+}
+
+impl Printer for SharedLines {
+    fn print(&mut self, s: &str) {
+        self.0.borrow_mut().push(s.to_string());
+    }
+}
+
+/// Byte sink shared with the test, e.g. for a trace.
+#[cfg(feature = "std")]
+#[derive(Clone, Default)]
+pub(crate) struct SharedBytes(pub Rc<RefCell<Vec<u8>>>);
+
+#[cfg(feature = "std")]
+impl SharedBytes {
+    /// Lines written so far.
+    pub fn lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.0.borrow())
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for SharedBytes {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// State whose mismatches are only `Relaxed`, as a `#[state(relaxed)]` field.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Soft(pub u8);
+
+impl AbstractState for Soft {
+    fn matches(&self, other: &Self) -> bool {
+        self == other
+    }
+    fn update(&mut self, other: &Self) {
+        self.0 = other.0;
+    }
+    fn mismatch_level(&self, other: &Self) -> Option<CheckLevel> {
+        (self != other).then_some(CheckLevel::Relaxed)
+    }
+}
+
+/// Command adding to a counter, returning the new count. Category `arith`.
+#[derive(Debug)]
+pub(crate) struct Add(pub u32);
+
+impl Command<u32> for Add {
+    fn execute(&self, state: &mut u32) -> isize {
+        *state += self.0;
+        *state as isize
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_le_bytes().to_vec()
+    }
+    fn category(&self) -> &'static str {
+        "arith"
+    }
+}
+
+/// Command resetting a counter, returning 0. Category `control`.
+#[derive(Debug)]
+pub(crate) struct Reset;
+
+impl Command<u32> for Reset {
+    fn execute(&self, state: &mut u32) -> isize {
+        *state = 0;
+        0
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        Vec::new()
+    }
+    fn category(&self) -> &'static str {
+        "control"
+    }
+}
+
+/// Commander replaying `commands` on a counter.
+pub(crate) fn replay<const N: usize>(commands: [Box<dyn Command<u32>>; N]) -> ReplayCommander<u32> {
+    ReplayCommander::new(commands.into())
+}
+
+/// Generators of `Add(1)` and `Reset`, named after the commands.
+pub(crate) fn generators() -> Vec<CommandGenerator<u32>> {
+    vec![
+        (String::from("Add"), Box::new(|| Box::new(Add(1)))),
+        (String::from("Reset"), Box::new(|| Box::new(Reset))),
+    ]
+}
+
+/// Strictly check one `command` per round from the `model` state against a
+/// target reporting each of the `observed` states in turn, the initial state
+/// first. Returns the error and the round it happened in.
+pub(crate) fn check_observed<S, C>(
+    model: S,
+    command: impl Fn() -> C,
+    observed: Vec<S>,
+) -> Result<(), (Error, usize)>
+where
+    S: AbstractState + core::fmt::Debug + 'static,
+    C: Command<S> + 'static,
+{
+    let commands = (1..observed.len())
+        .map(|_| Box::new(command()) as Box<dyn Command<S>>)
+        .collect();
+    let mut observed = observed.into_iter();
+    let port = ClosureTestPort::new(|_: &dyn Command<S>| 0, move || observed.next().unwrap());
+    let mut checker = Checker::new(ReplayCommander::new(commands), port, NullPrinter, model);
+    checker
+        .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+        .map_err(|e| (e, checker.round()))
+}
+
+/// In-process counter target, starting at 0, whose return value is off by
+/// one in the rounds listed in `wrong_retv`, counting from 1.
+pub(crate) fn counter_target(wrong_retv: &'static [usize]) -> impl TestPort<u32> {
+    let state = Rc::new(Cell::new(0));
+    let target = state.clone();
+    let mut round = 0;
+    ClosureTestPort::new(
+        move |command: &dyn Command<u32>| {
+            round += 1;
+            let mut value = target.get();
+            let retv = command.execute(&mut value);
+            target.set(value);
+            retv + wrong_retv.contains(&round) as isize
+        },
+        move || state.get(),
+    )
+}
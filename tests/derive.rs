@@ -0,0 +1,299 @@
+#![cfg(feature = "derive")]
+
+use km_checker::state::{FieldKind, Ignored, Range, Settling, Value, ValueList, ValueSet};
+use km_checker::{
+    AbstractState, CheckLevel, Checker, ClosureTestPort, Command, Error, Printer, ReplayCommander,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, AbstractState)]
+struct Device {
+    status: Settling<u8>,
+}
+
+#[test]
+fn observe_reaches_fields() {
+    let mut model = Device {
+        status: Settling::new(1, 1),
+    };
+    let target = Device {
+        status: Settling::new(0, 0),
+    };
+    assert!(target.matches(&model));
+    model.observe(&target);
+    assert!(!target.matches(&model));
+}
+
+#[derive(Debug, Clone, AbstractState)]
+struct Counters {
+    status: Settling<u8>,
+    free: Range<u32>,
+    #[state(relaxed)]
+    ticks: Value<u64>,
+}
+
+#[test]
+fn diff_names_mismatching_fields_only() {
+    let model = Counters {
+        status: Settling::new(1, 3),
+        free: Range::new(100, 5),
+        ticks: Value(7),
+    };
+    let target = Counters {
+        status: Settling::new(0, 0),
+        free: Range::new(90, 0),
+        ticks: Value(8),
+    };
+    assert_eq!(
+        target.diff(&model),
+        vec![".free: mismatch", ".ticks: expected 7, got 8"]
+    );
+    assert_eq!(target.mismatch_level(&model), Some(CheckLevel::Strict));
+}
+
+#[derive(Debug, Clone, AbstractState)]
+struct Task {
+    id: u32,
+    #[state(relaxed)]
+    runtime: u64,
+}
+
+#[test]
+fn relaxed_fields_stay_relaxed_in_containers() {
+    let model = Task { id: 1, runtime: 10 };
+    let target = Task { id: 1, runtime: 12 };
+    let relaxed = Some(CheckLevel::Relaxed);
+    assert_eq!(target.mismatch_level(&model), relaxed);
+    assert_eq!(
+        ValueList(vec![target.clone()]).mismatch_level(&ValueList(vec![model.clone()])),
+        relaxed
+    );
+    assert_eq!(
+        ValueSet(vec![target.clone()]).mismatch_level(&ValueSet(vec![model.clone()])),
+        relaxed
+    );
+    assert_eq!(Some(target).mismatch_level(&Some(model)), relaxed);
+}
+
+/// Register that cannot be updated to the invalid value `0xff`.
+#[derive(Debug, Clone, PartialEq)]
+struct Mode(u8);
+
+impl AbstractState for Mode {
+    fn matches(&self, other: &Self) -> bool {
+        self == other
+    }
+    fn update(&mut self, other: &Self) {
+        self.0 = other.0;
+    }
+    fn try_update(&mut self, other: &Self) -> Result<(), Error> {
+        if other.0 == 0xff {
+            return Err(Error::Io);
+        }
+        self.update(other);
+        Ok(())
+    }
+}
+
+/// Command running a task for 10 ticks.
+#[derive(Debug)]
+struct Run;
+
+impl Command<Task> for Run {
+    fn execute(&self, state: &mut Task) -> isize {
+        state.runtime += 10;
+        0
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+struct Quiet;
+
+impl Printer for Quiet {
+    fn print(&mut self, _s: &str) {}
+}
+
+#[test]
+fn relaxed_field_mismatch_does_not_fail_a_strict_check() {
+    let round = Rc::new(Cell::new(0));
+    let sent = round.clone();
+    // The target runs one tick late in round 1, and loses the task id in round 2.
+    let port = ClosureTestPort::new(
+        move |_: &dyn Command<Task>| {
+            sent.set(sent.get() + 1);
+            0
+        },
+        move || match round.get() {
+            0 => Task { id: 1, runtime: 0 },
+            1 => Task { id: 1, runtime: 11 },
+            _ => Task { id: 2, runtime: 20 },
+        },
+    );
+    let commander = ReplayCommander::new(vec![Box::new(Run) as Box<_>, Box::new(Run)]);
+    let mut checker = Checker::new(commander, port, Quiet, Task { id: 0, runtime: 0 });
+    assert_eq!(
+        checker.run(1, CheckLevel::Strict, CheckLevel::Strict),
+        Ok(())
+    );
+    assert_eq!(checker.mismatch_report().state_rounds, [1]);
+    assert_eq!(
+        checker.run(1, CheckLevel::Strict, CheckLevel::Strict),
+        Err(Error::StateMismatch)
+    );
+}
+
+#[derive(Debug, Clone, AbstractState)]
+struct Regs {
+    pc: Value<u32>,
+    mode: Mode,
+}
+
+#[derive(Debug, Clone, AbstractState)]
+struct Cpu {
+    regs: Regs,
+}
+
+#[test]
+fn failed_update_names_the_field() {
+    let mut model = Regs {
+        pc: Value(0),
+        mode: Mode(0),
+    };
+    let valid = Regs {
+        pc: Value(4),
+        mode: Mode(1),
+    };
+    assert_eq!(model.try_update(&valid), Ok(()));
+    assert!(model.matches(&valid));
+    let invalid = Regs {
+        pc: Value(8),
+        mode: Mode(0xff),
+    };
+    assert_eq!(model.try_update(&invalid), Err(Error::UpdateFailed("mode")));
+
+    // The innermost field name is kept.
+    let mut cpu = Cpu { regs: valid };
+    assert_eq!(
+        cpu.try_update(&Cpu { regs: invalid }),
+        Err(Error::UpdateFailed("mode"))
+    );
+}
+
+#[derive(Debug, Clone, AbstractState)]
+struct Process {
+    pid: Value<u32>,
+    children: ValueList<Value<u32>>,
+    stamp: Ignored<u64>,
+    regs: Regs,
+}
+
+#[test]
+fn field_kinds_report_checking_semantics() {
+    assert_eq!(
+        Process::field_kinds(),
+        [
+            ("pid", FieldKind::Value),
+            ("children", FieldKind::Value),
+            ("stamp", FieldKind::Ignored),
+            ("regs", FieldKind::Nested),
+        ]
+    );
+}
+
+#[derive(Debug, Clone, AbstractState)]
+enum Sched {
+    Idle,
+    Running(u32),
+    Blocked { on: u32, since: Ignored<u64> },
+}
+
+#[test]
+fn enum_variants_match_pairwise() {
+    assert!(Sched::Idle.matches(&Sched::Idle));
+    assert!(Sched::Running(1).matches(&Sched::Running(1)));
+    assert!(!Sched::Running(1).matches(&Sched::Running(2)));
+    let blocked = |on, since| Sched::Blocked {
+        on,
+        since: Ignored(since),
+    };
+    assert!(blocked(3, 10).matches(&blocked(3, 20)));
+    assert!(!blocked(3, 10).matches(&blocked(4, 10)));
+    assert_eq!(
+        blocked(3, 10).diff(&blocked(4, 10)),
+        [".on: expected 4, got 3"]
+    );
+
+    assert!(!Sched::Idle.matches(&Sched::Running(0)));
+    assert!(!Sched::Running(3).matches(&blocked(3, 0)));
+    assert_eq!(
+        Sched::Idle.diff(&Sched::Running(0)),
+        [": expected variant Running, got Idle"]
+    );
+    assert_eq!(
+        Sched::Idle.mismatch_level(&Sched::Running(0)),
+        Some(CheckLevel::Strict)
+    );
+}
+
+#[test]
+fn enum_update_switches_variants() {
+    let mut sched = Sched::Idle;
+    sched.update(&Sched::Running(5));
+    assert!(matches!(sched, Sched::Running(5)));
+    sched.update(&Sched::Running(6));
+    assert!(matches!(sched, Sched::Running(6)));
+}
+
+#[derive(Debug, Clone, AbstractState)]
+struct Queue {
+    len: u32,
+    #[state(skip)]
+    cursor: usize,
+}
+
+#[test]
+fn skipped_fields_are_not_checked_or_updated() {
+    let mut model = Queue { len: 2, cursor: 0 };
+    let target = Queue { len: 2, cursor: 7 };
+    assert!(model.matches(&target));
+    assert!(model.diff(&target).is_empty());
+    assert_eq!(model.mismatch_level(&target), None);
+    assert!(!model.matches(&Queue { len: 3, cursor: 0 }));
+    model.update(&Queue { len: 4, cursor: 9 });
+    assert_eq!((model.len, model.cursor), (4, 0));
+}
+
+#[derive(Debug, Clone, AbstractState)]
+struct Proc {
+    pid: u32,
+    child: Option<Box<Proc>>,
+}
+
+fn chain(pids: &[u32]) -> Option<Box<Proc>> {
+    let (&pid, rest) = pids.split_first()?;
+    Some(Box::new(Proc {
+        pid,
+        child: chain(rest),
+    }))
+}
+
+#[test]
+fn boxed_nested_states_match_recursively() {
+    let model = chain(&[1, 2, 3]).unwrap();
+    assert!(model.matches(&chain(&[1, 2, 3]).unwrap()));
+    let target = chain(&[1, 2, 4]).unwrap();
+    assert!(!model.matches(&target));
+    assert_eq!(model.diff(&target), [".child.child.pid: expected 4, got 3"]);
+    assert!(!model.matches(&chain(&[1, 2]).unwrap()));
+    assert_eq!(
+        model.diff(&chain(&[1, 2]).unwrap()),
+        [".child.child: expected None, got Some(..)"]
+    );
+
+    let mut model = model;
+    model.update(&target);
+    assert!(model.matches(&target));
+}
@@ -0,0 +1,41 @@
+//! Matching empty collections must not allocate. Kept in its own test binary,
+//! as the allocation counter is global.
+
+use km_checker::state::{Value, ValueList, ValueMap, ValueSet, ValueSubset};
+use km_checker::AbstractState;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// System allocator counting allocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn empty_collections_match_without_allocating() {
+    let list = ValueList::<Value<u8>>(Vec::new());
+    let set = ValueSet::<Value<u8>>(Vec::new());
+    let subset = ValueSubset::<Value<u8>>(Vec::new());
+    let map = ValueMap::<u8, Value<u8>>(BTreeMap::new());
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let matched = list.matches(&list.clone())
+        && set.matches(&set.clone())
+        && subset.matches(&subset.clone())
+        && map.matches(&map.clone());
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), before);
+    assert!(matched);
+}
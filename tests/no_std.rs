@@ -0,0 +1,52 @@
+//! The checker is usable from `no_std` code with only `alloc`. Run with
+//! `--no-default-features` to check the crate itself builds without `std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use km_checker::{CheckLevel, Checker, Command, MockTestPort, Printer, ReplayCommander};
+
+#[derive(Debug)]
+struct Insert(u32);
+
+impl Command<BTreeMap<u32, u32>> for Insert {
+    fn execute(&self, state: &mut BTreeMap<u32, u32>) -> isize {
+        state.insert(self.0, self.0 * 2);
+        state.len() as isize
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_le_bytes().to_vec()
+    }
+}
+
+struct Lines(Vec<String>);
+
+impl Printer for Lines {
+    fn print(&mut self, s: &str) {
+        self.0.push(String::from(s));
+    }
+}
+
+#[test]
+fn checks_without_std() {
+    let commands = (0..4)
+        .map(|i| Box::new(Insert(i)) as Box<dyn Command<_>>)
+        .collect();
+    let mut checker = Checker::new(
+        ReplayCommander::new(commands),
+        MockTestPort::new(BTreeMap::new()),
+        Lines(Vec::new()),
+        BTreeMap::new(),
+    );
+    checker
+        .run(usize::MAX, CheckLevel::Strict, CheckLevel::Strict)
+        .unwrap();
+    assert!(checker.is_finished());
+    assert_eq!(checker.state().len(), 4);
+    assert_eq!(checker.stats().rounds, 4);
+}